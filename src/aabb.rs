@@ -0,0 +1,92 @@
+//! Axis-aligned bounding boxes.
+
+use crate::{Point3, Ray};
+
+/// An axis-aligned box that bounds an object, to quickly skip the rays
+/// that cannot hit it.
+///
+/// TODO: Like `HitRecord`, this is only public because `Object` is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub(crate) min: Point3,
+    pub(crate) max: Point3,
+}
+
+impl Aabb {
+    /// The box with two opposite corners. It is padded a bit, so that
+    /// a flat object (e.g. a quad) still has a box with volume.
+    pub(crate) fn new(a: Point3, b: Point3) -> Self {
+        const PADDING: f64 = 1e-4;
+
+        let (mut min, mut max) = ([0.0; 3], [0.0; 3]);
+        for axis in 0..3 {
+            let (lo, hi) = (a[axis].min(b[axis]), a[axis].max(b[axis]));
+            let pad = if hi - lo < PADDING {
+                PADDING / 2.0
+            } else {
+                0.0
+            };
+            min[axis] = lo - pad;
+            max[axis] = hi + pad;
+        }
+
+        Self {
+            min: Point3::new(min[0], min[1], min[2]),
+            max: Point3::new(max[0], max[1], max[2]),
+        }
+    }
+
+    /// The smallest box containing both boxes.
+    pub(crate) fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: Point3::new(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Point3::new(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+
+    /// The 8 corners of the box.
+    pub(crate) fn corners(&self) -> [Point3; 8] {
+        let pick = |i: usize, axis: usize| {
+            if i >> axis & 1 == 0 {
+                self.min[axis]
+            } else {
+                self.max[axis]
+            }
+        };
+
+        std::array::from_fn(|i| Point3::new(pick(i, 0), pick(i, 1), pick(i, 2)))
+    }
+
+    pub(crate) fn centroid(&self) -> Point3 {
+        self.min + 0.5 * (self.max - self.min)
+    }
+
+    /// Whether the ray hits the box within the time interval,
+    /// by the slab method.
+    pub(crate) fn hit_by(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        let (origin, direction) = (ray.origin(), ray.direction());
+
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let t0 = (self.min[axis] - origin[axis]) * inv;
+            let t1 = (self.max[axis] - origin[axis]) * inv;
+            let (t0, t1) = if inv < 0.0 { (t1, t0) } else { (t0, t1) };
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,247 @@
+//! Camera animation by keyframes.
+//!
+//! A `CameraPath` holds the cameras being built at some times, and moves
+//! the viewpoint, the point looked at, the field-of-view and the focus
+//! distance between them. The other settings are those of the last key
+//! at or before the time, so they change at once.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::{CameraBuilder, Point3, Vec3};
+
+/// How the values are interpolated between keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Straight lines between the keys, which turn sharply at them.
+    #[default]
+    Linear,
+
+    /// A Catmull-Rom spline through the keys, which turns smoothly.
+    CatmullRom,
+}
+
+/// The keyframes of a camera, e.g. for a turntable or a fly-through.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    /// The keys in the order of their times.
+    keys: Vec<(f64, CameraBuilder)>,
+
+    interpolation: Interpolation,
+
+    /// Whether the last key is the first of the next turn.
+    looped: bool,
+}
+
+/// The values interpolated between keys.
+#[derive(Clone, Copy)]
+struct Values {
+    look_from: Vec3,
+    look_at: Vec3,
+    vfov: f64,
+    focus_dist: f64,
+}
+
+impl Values {
+    fn of(camera: &CameraBuilder) -> Self {
+        let params = &camera.params;
+        let focus_dist = camera
+            .focus_dist
+            .unwrap_or_else(|| (params.look_at - params.look_from).length());
+
+        Self {
+            look_from: params.look_from - Point3::default(),
+            look_at: params.look_at - Point3::default(),
+            vfov: params.vfov,
+            focus_dist,
+        }
+    }
+}
+
+impl CameraPath {
+    /// Starts a path with a camera at a time, which stays there
+    /// until another key is added. The interpolation is linear.
+    pub fn new(time: f64, camera: CameraBuilder) -> Self {
+        assert!(time.is_finite(), "key time must be finite");
+
+        Self {
+            keys: vec![(time, camera)],
+            interpolation: Interpolation::default(),
+            looped: false,
+        }
+    }
+
+    /// Adds a key, replacing any other at the same time.
+    pub fn key(mut self, time: f64, camera: CameraBuilder) -> Self {
+        assert!(time.is_finite(), "key time must be finite");
+
+        let i = self.keys.partition_point(|&(t, _)| t < time);
+        match self.keys.get_mut(i) {
+            Some(key) if key.0 == time => key.1 = camera,
+            _ => self.keys.insert(i, (time, camera)),
+        }
+        self
+    }
+
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Closes the path into a loop, whose last key is the same as the first,
+    /// e.g. for a turntable. A spline then turns smoothly through them, and
+    /// the last key is left out of the frames as the first of the next turn.
+    pub fn looped(mut self, looped: bool) -> Self {
+        self.looped = looped;
+        self
+    }
+
+    pub fn is_looped(&self) -> bool {
+        self.looped
+    }
+
+    /// The times of the first and the last key.
+    pub fn time_range(&self) -> (f64, f64) {
+        (self.keys[0].0, self.keys[self.keys.len() - 1].0)
+    }
+
+    /// The camera at a time, which stays at the first key before it
+    /// and at the last key after it.
+    pub fn at(&self, time: f64) -> CameraBuilder {
+        // the keys from the one at or before the time
+        let i = self.keys.partition_point(|&(t, _)| t <= time);
+        if i == 0 {
+            return self.keys[0].1.clone();
+        }
+        if i == self.keys.len() {
+            return self.keys[i - 1].1.clone();
+        }
+
+        // the two keys around the time, and their neighbors if any
+        let last = self.keys.len() - 1;
+        let neighbors = [i.saturating_sub(2), i - 1, i, (i + 1).min(last)];
+        let mut times = neighbors.map(|j| self.keys[j].0);
+        let mut values = neighbors.map(|j| Values::of(&self.keys[j].1));
+        if self.looped && last >= 2 {
+            // across the seam, the neighbors are the keys of the next
+            // or the previous turn
+            let period = self.keys[last].0 - self.keys[0].0;
+            if i == 1 {
+                times[0] = self.keys[last - 1].0 - period;
+                values[0] = Values::of(&self.keys[last - 1].1);
+            }
+            if i == last {
+                times[3] = self.keys[1].0 + period;
+                values[3] = Values::of(&self.keys[1].1);
+            }
+        }
+        let s = (time - times[1]) / (times[2] - times[1]);
+
+        let way = self.interpolation;
+        let mut camera = self.keys[i - 1].1.clone();
+        let look_from = way.interpolate(values.map(|v| v.look_from), times, s);
+        let look_at = way.interpolate(values.map(|v| v.look_at), times, s);
+        camera.params.look_from = Point3::default() + look_from;
+        camera.params.look_at = Point3::default() + look_at;
+        camera.params.vfov = way.interpolate(values.map(|v| v.vfov), times, s);
+        camera.focus_dist = Some(way.interpolate(values.map(|v| v.focus_dist), times, s));
+        camera
+    }
+}
+
+impl Interpolation {
+    /// Interpolates between `p[1]` and `p[2]` at their times `t`,
+    /// with their neighbors for a spline.
+    fn interpolate<T>(self, p: [T; 4], t: [f64; 4], s: f64) -> T
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+    {
+        match self {
+            Self::Linear => p[1] * (1.0 - s) + p[2] * s,
+            Self::CatmullRom => catmull_rom(p, t, s),
+        }
+    }
+}
+
+/// Interpolates between `p[1]` and `p[2]` at their times, by a cubic
+/// Hermite spline whose tangents are the slopes between the neighbors.
+/// A key at the end of an open path is its own neighbor.
+fn catmull_rom<T>(p: [T; 4], t: [f64; 4], s: f64) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+{
+    let dt = t[2] - t[1];
+    let m1 = (p[2] - p[0]) * (dt / (t[2] - t[0]));
+    let m2 = (p[3] - p[1]) * (dt / (t[3] - t[1]));
+
+    let (s2, s3) = (s * s, s * s * s);
+    p[1] * (2.0 * s3 - 3.0 * s2 + 1.0)
+        + m1 * (s3 - 2.0 * s2 + s)
+        + p[2] * (-2.0 * s3 + 3.0 * s2)
+        + m2 * (s3 - s2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A camera on a circle of radius 10 around the origin at an angle.
+    fn around(degrees: f64) -> CameraBuilder {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        CameraBuilder::new(Point3::new(10.0 * cos, 0.0, 10.0 * sin), Point3::default())
+            .vfov(30.0 + degrees / 10.0)
+            .focus_dist(10.0)
+    }
+
+    /// A turn in 4 seconds through keys every 90°.
+    fn turn(interpolation: Interpolation) -> CameraPath {
+        (1..=4)
+            .fold(CameraPath::new(0.0, around(0.0)), |path, i| {
+                path.key(i as f64, around(90.0 * i as f64))
+            })
+            .interpolation(interpolation)
+    }
+
+    #[test]
+    fn through_keys() {
+        for interpolation in [Interpolation::Linear, Interpolation::CatmullRom] {
+            for looped in [false, true] {
+                let path = turn(interpolation).looped(looped);
+                for i in 0..=4 {
+                    assert_eq!(path.at(i as f64), around(90.0 * i as f64));
+                }
+                assert_eq!(path.at(-1.0), around(0.0));
+                assert_eq!(path.at(5.0), around(360.0));
+            }
+        }
+    }
+
+    #[test]
+    fn linear_between_keys() {
+        let camera = turn(Interpolation::Linear).at(0.25).params;
+        assert_eq!(camera.look_from, Point3::new(7.5, 0.0, 2.5));
+        assert_eq!(camera.vfov, 32.25);
+    }
+
+    #[test]
+    fn key_replaces_same_time() {
+        let path = turn(Interpolation::Linear).key(2.0, around(45.0));
+        assert_eq!(path.time_range(), (0.0, 4.0));
+        assert_eq!(path.at(2.0), around(45.0));
+        assert_eq!(path.keys.len(), 5);
+    }
+
+    #[test]
+    fn smooth_loop_seam() {
+        // the difference between the steps into and out of the first key
+        let kink = |path: &CameraPath| {
+            let at = |time: f64| path.at(time).params.look_from;
+            let into = at(4.0) - at(3.99);
+            let out = at(0.01) - at(0.0);
+            (out - into).length()
+        };
+
+        let path = turn(Interpolation::CatmullRom);
+        assert!(kink(&path.clone().looped(true)) < 0.01);
+        assert!(kink(&path) > 0.05);
+    }
+}
@@ -0,0 +1,140 @@
+//! Bounding volume hierarchies.
+//!
+//! A BVH is a binary tree of bounding boxes over a list of objects. A ray
+//! only visits the subtrees whose boxes it hits, so finding the closest hit
+//! takes roughly logarithmic rather than linear time in the number of objects.
+//!
+//! The tree only knows the objects by their boxes and indices, so it serves
+//! the objects of a world as well as the triangles of a mesh.
+
+use crate::aabb::Aabb;
+use crate::object::HitRecord;
+use crate::Ray;
+
+/// A node of the tree, stored in depth-first order.
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    /// An object, by its index in the list.
+    Leaf { bbox: Aabb, object: usize },
+
+    /// A subtree, whose left child is the next node.
+    Branch { bbox: Aabb, right: usize },
+}
+
+impl Node {
+    fn bbox(&self) -> &Aabb {
+        match self {
+            Self::Leaf { bbox, .. } | Self::Branch { bbox, .. } => bbox,
+        }
+    }
+}
+
+/// A BVH over a list of objects, which it refers to by index.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+}
+
+impl Bvh {
+    /// Builds the tree by recursively splitting the objects in half,
+    /// by the centers of their boxes along the widest axis.
+    pub(crate) fn new(boxes: impl IntoIterator<Item = Aabb>) -> Self {
+        let mut leaves: Vec<_> = boxes.into_iter().zip(0..).collect();
+
+        let mut bvh = Self::default();
+        if !leaves.is_empty() {
+            bvh.build(&mut leaves);
+        }
+
+        bvh
+    }
+
+    fn build(&mut self, leaves: &mut [(Aabb, usize)]) {
+        if let [(bbox, object)] = *leaves {
+            self.nodes.push(Node::Leaf { bbox, object });
+            return;
+        }
+
+        let bbox = leaves
+            .iter()
+            .skip(1)
+            .fold(leaves[0].0, |acc, (bbox, _)| acc.union(bbox));
+
+        // the widest axis of the centers
+        let centers = leaves
+            .iter()
+            .map(|(bbox, _)| Aabb::new(bbox.centroid(), bbox.centroid()))
+            .reduce(|acc, bbox| acc.union(&bbox))
+            .unwrap();
+        let extent = centers.max - centers.min;
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+            .unwrap();
+
+        let mid = leaves.len() / 2;
+        leaves.select_nth_unstable_by(mid, |(a, _), (b, _)| {
+            a.centroid()[axis].total_cmp(&b.centroid()[axis])
+        });
+
+        let branch = self.nodes.len();
+        self.nodes.push(Node::Branch { bbox, right: 0 });
+
+        let (left, right) = leaves.split_at_mut(mid);
+        self.build(left);
+        let right_index = self.nodes.len();
+        self.build(right);
+
+        self.nodes[branch] = Node::Branch {
+            bbox,
+            right: right_index,
+        };
+    }
+
+    /// The bounding box of all objects.
+    pub(crate) fn bounding_box(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| *node.bbox())
+    }
+
+    /// The closest hit among the objects, with the index of the hit object,
+    /// given how to hit an object by its index before a maximum time.
+    pub(crate) fn hit_by<'a>(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        mut t_max: f64,
+        hit: impl Fn(usize, f64) -> Option<HitRecord<'a>>,
+    ) -> Option<(HitRecord<'a>, usize)> {
+        let mut closest = None;
+
+        // the tree is balanced, so its depth is far below the stack size
+        let mut stack = [0; 64];
+        let mut len = 0usize;
+        let mut next = (!self.nodes.is_empty()).then_some(0);
+
+        while let Some(i) = next.take().or_else(|| {
+            len = len.checked_sub(1)?;
+            Some(stack[len])
+        }) {
+            let node = &self.nodes[i];
+            if !node.bbox().hit_by(ray, t_min, t_max) {
+                continue;
+            }
+
+            match *node {
+                Node::Leaf { object, .. } => {
+                    if let Some(rec) = hit(object, t_max) {
+                        t_max = rec.t;
+                        closest = Some((rec, object));
+                    }
+                }
+                Node::Branch { right, .. } => {
+                    stack[len] = right;
+                    len += 1;
+                    next = Some(i + 1);
+                }
+            }
+        }
+
+        closest
+    }
+}
@@ -1,8 +1,104 @@
-//! A portable camera with aperture simulation.
+//! Cameras, which map points of the image to rays into the world.
+//!
+//! Besides the thin-lens `Perspective` camera with a `Lens`, there
+//! are an `Orthographic` camera for elevations and plans, a `Fisheye` with the
+//! equidistant projection, and an `Equirectangular` panorama of all directions
+//! for VR. All of them are built by a `CameraBuilder`.
 
-use crate::{Point3, Ray, RngExt, Vec3};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Aperture, Lens, Point3, Ray, Vec3, PI};
+
+/// A camera, which can be shared by the threads of a renderer.
+pub trait Camera: Sync + Send {
+    /// Gets the ray passing through a point of the image, specified by its
+    /// relative width and height from the lower left corner, or `None` if
+    /// the projection doesn't cover the point, which is then black.
+    ///
+    /// `lens` is a sample in [0, 1)², for the cameras with an aperture.
+    fn get_ray(&self, u: f64, v: f64, lens: (f64, f64)) -> Option<Ray>;
+}
+
+/// The parameters of a camera, see `CameraBuilder` for their meanings.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CameraParams {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    pub aspect_ratio: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lens: Lens,
+}
+
+/// The orthonormal frame of a camera at its viewpoint.
+#[derive(Debug, Clone, Copy)]
+struct View {
+    origin: Point3,
+    right: Vec3,
+    up: Vec3,
+    forward: Vec3,
+}
+
+impl View {
+    /// The frame looking at a point, with `vup` turned perpendicular to
+    /// the direction looked to.
+    fn new(look_from: Point3, look_at: Point3, vup: Vec3) -> Result<Self, CameraError> {
+        // NaN fails all the checks too
+        let look_to = look_at - look_from;
+        if !look_to.length_squared().is_normal() {
+            return Err(CameraError::NoDirection);
+        }
+        let sin2 = look_to.unit().cross(&vup.unit()).length_squared();
+        if sin2.is_nan() || sin2 < 1e-12 {
+            return Err(CameraError::ParallelUp);
+        }
+
+        let forward = look_to.unit();
+        let right = forward.cross(&vup).unit();
+
+        Ok(Self {
+            origin: look_from,
+            right,
+            up: right.cross(&forward),
+            forward,
+        })
+    }
+
+    /// The frame with `vup` as is, and the direction looked to
+    /// turned level instead.
+    fn level(look_from: Point3, look_at: Point3, vup: Vec3) -> Result<Self, CameraError> {
+        let Self { right, .. } = Self::new(look_from, look_at, vup)?;
+        let up = vup.unit();
+
+        Ok(Self {
+            origin: look_from,
+            right,
+            up,
+            forward: up.cross(&right),
+        })
+    }
+}
+
+/// A perspective camera with a thin lens, which is (de)serialized
+/// by its parameters.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "CameraParams", into = "CameraParams")
+)]
+pub struct Perspective {
+    /// The parameters the camera is created from.
+    params: CameraParams,
 
-pub struct Camera {
     /// The viewpoint.
     origin: Point3,
 
@@ -19,79 +115,761 @@ pub struct Camera {
     /// The larger it is, the shallower DoF will be.
     /// With 0 radius it is an ideal pinhole camera.
     lens_radius: f64,
+
+    /// The normal of the tilted plane of focus, and its distance
+    /// from the viewpoint, if the lens is tilted.
+    focal_plane: Option<(Vec3, f64)>,
+}
+
+impl Perspective {
+    pub fn params(&self) -> CameraParams {
+        self.params.clone()
+    }
+}
+
+impl Camera for Perspective {
+    fn get_ray(&self, u: f64, v: f64, lens: (f64, f64)) -> Option<Ray> {
+        // sample offset vector parallel to the viewport plane,
+        // from the aperture
+        let (x, y) = self.params.lens.sample(lens);
+        let offset = (x * self.horizontal.unit() + y * self.vertical.unit()) * self.lens_radius;
+
+        // the point in focus, on the ray through the center of the lens
+        let mut target = self.lower_left_corner + u * self.horizontal + v * self.vertical;
+        if let Some((normal, dist)) = self.focal_plane {
+            let direction = target - self.origin;
+            let t = dist / normal.dot(&direction);
+            if !(t > 0.0 && t.is_finite()) {
+                // the ray never meets the plane, so the focus is at infinity
+                return Some(Ray::new(self.origin + offset, direction));
+            }
+            target = self.origin + t * direction;
+        }
+
+        Some(Ray::new(
+            self.origin + offset,
+            target - self.origin - offset,
+        ))
+    }
+}
+
+/// An orthographic camera, whose rays are all parallel to the direction
+/// looked to, starting from the plane of the viewpoint. Objects keep their
+/// sizes at any distance, e.g. for the elevations of a building.
+///
+/// It is (de)serialized by its parameters and the height of the view.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "OrthographicParams", into = "OrthographicParams")
+)]
+pub struct Orthographic {
+    params: CameraParams,
+    view: View,
+
+    /// The size of the view in the world.
+    width: f64,
+    height: f64,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct OrthographicParams {
+    #[serde(flatten)]
+    params: CameraParams,
+    height: f64,
+}
+
+impl Orthographic {
+    pub fn params(&self) -> CameraParams {
+        self.params.clone()
+    }
+
+    /// The height of the view in the world.
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+}
+
+impl Camera for Orthographic {
+    fn get_ray(&self, u: f64, v: f64, _lens: (f64, f64)) -> Option<Ray> {
+        let View {
+            origin,
+            right,
+            up,
+            forward,
+        } = self.view;
+        let offset = (u - 0.5) * self.width * right + (v - 0.5) * self.height * up;
+
+        Some(Ray::new(origin + offset, forward))
+    }
+}
+
+/// A fisheye camera with the equidistant projection, where the angle from
+/// the direction looked to grows with the distance from the center of the
+/// image. Beyond 180° from that direction the image is black.
+///
+/// It is (de)serialized by its parameters.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "CameraParams", into = "CameraParams")
+)]
+pub struct Fisheye {
+    params: CameraParams,
+    view: View,
+
+    /// The field-of-view across the height of the image, in radians.
+    fov: f64,
+
+    aspect_ratio: f64,
+}
+
+impl Fisheye {
+    pub fn params(&self) -> CameraParams {
+        self.params.clone()
+    }
 }
 
-impl Camera {
-    pub fn new(
-        // Your viewpoint.
-        look_from: Point3,
-
-        // The point you are looking at.
-        // Together with the viewpoint, we can determine the direction we are
-        // looking to, thus the viewport plane is fixed...
-        look_at: Point3,
-
-        // ...But the viewport can rotate in this plane, so we need a "up"
-        // direction. This `vup` doesn't need to be in the plane; The code
-        // handle the projection, so most of the time you can just pass in
-        // (0, 1, 0).
-        vup: Vec3,
-
-        // The vertical field-of-view, in degrees.
-        vfov: f64,
-
-        // The aspect ratio of the viewport. Normally this should be the same
-        // as the aspect ratio of the image, but you can change it to
-        // stretch the image.
-        aspect_ratio: f64,
-
-        // The aperture of the camera. The larger it is, the shallower
-        // the DoF will be. Set it to 0 for an ideal camera with no blurring.
-        aperture: f64,
-
-        // The focus distance of the camera, i.e. if you travel such distance
-        // from the viewpoint to the direction you are looking to, the object
-        // at that point will be perfectly clear no matter the aperture.
-        // Normally this should be set to the distance between `look_from`
-        // and `look_at` for a clear view in the middle.
-        focus_dist: f64,
-    ) -> Self {
+impl Camera for Fisheye {
+    fn get_ray(&self, u: f64, v: f64, _lens: (f64, f64)) -> Option<Ray> {
+        let View {
+            origin,
+            right,
+            up,
+            forward,
+        } = self.view;
+
+        // the offset from the center, with the height of the image as 1
+        let (x, y) = ((u - 0.5) * self.aspect_ratio, v - 0.5);
+        let r = x.hypot(y);
+        let theta = r * self.fov;
+        if theta > PI {
+            return None;
+        }
+
+        let direction = if r > 0.0 {
+            theta.cos() * forward + theta.sin() / r * (x * right + y * up)
+        } else {
+            forward
+        };
+
+        Some(Ray::new(origin, direction))
+    }
+}
+
+/// An equirectangular panorama of all directions, for an image of 2:1, with
+/// the longitude across its width and the latitude across its height. The
+/// poles are along `vup`, so that the horizon is level, and the center of
+/// the image is towards the point looked at.
+///
+/// It is (de)serialized by its parameters, of which it only uses
+/// `look_from`, `look_at` and `vup`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "CameraParams", into = "CameraParams")
+)]
+pub struct Equirectangular {
+    params: CameraParams,
+    view: View,
+}
+
+impl Equirectangular {
+    pub fn params(&self) -> CameraParams {
+        self.params.clone()
+    }
+}
+
+impl Camera for Equirectangular {
+    fn get_ray(&self, u: f64, v: f64, _lens: (f64, f64)) -> Option<Ray> {
+        let View {
+            origin,
+            right,
+            up,
+            forward,
+        } = self.view;
+
+        let longitude = (u - 0.5) * 2.0 * PI;
+        let latitude = (v - 0.5) * PI;
+        let level = longitude.sin() * right + longitude.cos() * forward;
+
+        Some(Ray::new(
+            origin,
+            latitude.cos() * level + latitude.sin() * up,
+        ))
+    }
+}
+
+/// A camera being built, with the defaults of a pinhole camera:
+/// `vup` of (0, 1, 0), 90° vertical field-of-view, 16:9 aspect ratio,
+/// no aperture, and focus at the point looked at.
+///
+/// The settings that a projection has no use for are ignored, e.g.
+/// the aperture and lens of all but the perspective camera.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraBuilder {
+    pub(crate) params: CameraParams,
+
+    /// The focus distance, if not the distance to the point looked at.
+    pub(crate) focus_dist: Option<f64>,
+}
+
+impl CameraBuilder {
+    /// Starts building a camera at a viewpoint, looking at a point.
+    pub fn new(look_from: Point3, look_at: Point3) -> Self {
+        Self {
+            params: CameraParams {
+                look_from,
+                look_at,
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vfov: 90.0,
+                aspect_ratio: 16.0 / 9.0,
+                aperture: 0.0,
+                focus_dist: 0.0,
+                lens: Lens::default(),
+            },
+            focus_dist: None,
+        }
+    }
+
+    /// Moves the viewpoint.
+    pub fn look_from(mut self, look_from: Point3) -> Self {
+        self.params.look_from = look_from;
+        self
+    }
+
+    /// Looks at another point.
+    pub fn look_at(mut self, look_at: Point3) -> Self {
+        self.params.look_at = look_at;
+        self
+    }
+
+    /// The "up" direction, which rotates the viewport around the direction
+    /// looked to. It doesn't need to be perpendicular to that direction,
+    /// only not parallel to it.
+    pub fn vup(mut self, vup: Vec3) -> Self {
+        self.params.vup = vup;
+        self
+    }
+
+    /// The vertical field-of-view, in degrees.
+    pub fn vfov(mut self, vfov: f64) -> Self {
+        self.params.vfov = vfov;
+        self
+    }
+
+    /// The aspect ratio of the viewport. Normally this should be the same
+    /// as the aspect ratio of the image, but you can change it to
+    /// stretch the image.
+    pub fn aspect_ratio(mut self, aspect_ratio: f64) -> Self {
+        self.params.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    /// The aperture of the camera. The larger it is, the shallower
+    /// the DoF will be. Set it to 0 for an ideal camera with no blurring.
+    pub fn aperture(mut self, aperture: f64) -> Self {
+        self.params.aperture = aperture;
+        self
+    }
+
+    /// The focus distance of the camera, i.e. the object at such distance
+    /// along the direction looked to is perfectly clear no matter the
+    /// aperture. By default it is the point looked at.
+    pub fn focus_dist(mut self, focus_dist: f64) -> Self {
+        self.focus_dist = Some(focus_dist);
+        self
+    }
+
+    /// The shape of the aperture, round by default.
+    pub fn aperture_shape(mut self, shape: Aperture) -> Self {
+        self.params.lens.aperture = shape;
+        self
+    }
+
+    /// The anamorphic squeeze of the lens, see `Lens::squeeze`, which widens
+    /// the view horizontally by a factor onto the same viewport, and stretches
+    /// the bokeh vertically.
+    pub fn squeeze(mut self, squeeze: f64) -> Self {
+        self.params.lens.squeeze = squeeze;
+        self
+    }
+
+    /// Tilts the plane of focus around the horizontal axis by an angle in
+    /// degrees, as with a tilt-shift lens, see `Lens::tilt`. It still goes
+    /// through the point in focus in the middle of the image.
+    pub fn tilt(mut self, tilt: f64) -> Self {
+        self.params.lens.tilt = tilt;
+        self
+    }
+
+    /// Builds a perspective camera, or fails if the parameters
+    /// are degenerate.
+    pub fn build(self) -> Result<Perspective, CameraError> {
+        Perspective::try_from(self.into_params())
+    }
+
+    /// Builds an orthographic camera, whose view is `height` high
+    /// in the world.
+    pub fn build_orthographic(self, height: f64) -> Result<Orthographic, CameraError> {
+        let view = self.view()?;
+        let aspect_ratio = self.checked_aspect_ratio()?;
+        if !(height > 0.0 && height.is_finite()) {
+            return Err(CameraError::Height(height));
+        }
+
+        Ok(Orthographic {
+            params: self.into_params(),
+            view,
+            width: aspect_ratio * height,
+            height,
+        })
+    }
+
+    /// Builds a fisheye camera, with `vfov` up to 360 degrees.
+    pub fn build_fisheye(self) -> Result<Fisheye, CameraError> {
+        let view = self.view()?;
+        let aspect_ratio = self.checked_aspect_ratio()?;
+        let vfov = self.params.vfov;
+        if !(vfov > 0.0 && vfov <= 360.0) {
+            return Err(CameraError::FieldOfView(vfov));
+        }
+
+        Ok(Fisheye {
+            params: self.into_params(),
+            view,
+            fov: vfov.to_radians(),
+            aspect_ratio,
+        })
+    }
+
+    /// Builds an equirectangular panorama, which has no field-of-view
+    /// and is always 2:1.
+    pub fn build_equirectangular(self) -> Result<Equirectangular, CameraError> {
+        let CameraParams {
+            look_from,
+            look_at,
+            vup,
+            ..
+        } = self.params;
+
+        Ok(Equirectangular {
+            view: View::level(look_from, look_at, vup)?,
+            params: self.into_params(),
+        })
+    }
+
+    /// The parameters, with the focus distance resolved.
+    fn into_params(self) -> CameraParams {
+        let focus_dist = self
+            .focus_dist
+            .unwrap_or_else(|| (self.params.look_at - self.params.look_from).length());
+
+        CameraParams {
+            focus_dist,
+            ..self.params
+        }
+    }
+
+    fn view(&self) -> Result<View, CameraError> {
+        View::new(self.params.look_from, self.params.look_at, self.params.vup)
+    }
+
+    fn checked_aspect_ratio(&self) -> Result<f64, CameraError> {
+        match self.params.aspect_ratio {
+            ratio if ratio > 0.0 && ratio.is_finite() => Ok(ratio),
+            ratio => Err(CameraError::AspectRatio(ratio)),
+        }
+    }
+}
+
+/// A degenerate configuration of a camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraError {
+    /// The viewpoint is the point looked at, so there is no direction.
+    NoDirection,
+
+    /// The "up" direction is zero or parallel to the direction looked to.
+    ParallelUp,
+
+    /// The vertical field-of-view is not in (0, 180) degrees,
+    /// or (0, 360] for a fisheye.
+    FieldOfView(f64),
+
+    /// The aspect ratio is not positive.
+    AspectRatio(f64),
+
+    /// The aperture is negative.
+    Aperture(f64),
+
+    /// The focus distance is not positive.
+    FocusDistance(f64),
+
+    /// The height of an orthographic view is not positive.
+    Height(f64),
+
+    /// A polygonal aperture has fewer than 3 blades.
+    Blades(u32),
+
+    /// The anamorphic squeeze is not positive.
+    Squeeze(f64),
+
+    /// The tilt of the plane of focus is not in (-90, 90) degrees.
+    Tilt(f64),
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoDirection => write!(f, "the camera looks at its own viewpoint"),
+            Self::ParallelUp => write!(f, "vup is parallel to the view direction"),
+            Self::FieldOfView(vfov) => write!(f, "vfov {} is out of range", vfov),
+            Self::AspectRatio(ratio) => write!(f, "aspect ratio {} is not positive", ratio),
+            Self::Aperture(aperture) => write!(f, "aperture {} is negative", aperture),
+            Self::FocusDistance(dist) => write!(f, "focus distance {} is not positive", dist),
+            Self::Height(height) => write!(f, "view height {} is not positive", height),
+            Self::Blades(blades) => write!(f, "an aperture of {} blades is not a polygon", blades),
+            Self::Squeeze(squeeze) => write!(f, "squeeze {} is not positive", squeeze),
+            Self::Tilt(tilt) => write!(f, "tilt {} is not in (-90, 90) degrees", tilt),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+impl TryFrom<CameraParams> for Perspective {
+    type Error = CameraError;
+
+    fn try_from(params: CameraParams) -> Result<Self, CameraError> {
+        let CameraParams {
+            look_from,
+            look_at,
+            vup,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_dist,
+            ref lens,
+        } = params;
+
+        let view = View::new(look_from, look_at, vup)?;
+        if !(vfov > 0.0 && vfov < 180.0) {
+            return Err(CameraError::FieldOfView(vfov));
+        }
+        if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+            return Err(CameraError::AspectRatio(aspect_ratio));
+        }
+        if !(aperture >= 0.0 && aperture.is_finite()) {
+            return Err(CameraError::Aperture(aperture));
+        }
+        if !(focus_dist > 0.0 && focus_dist.is_finite()) {
+            return Err(CameraError::FocusDistance(focus_dist));
+        }
+        if let Aperture::Polygon { blades, .. } = lens.aperture {
+            if blades < 3 {
+                return Err(CameraError::Blades(blades));
+            }
+        }
+        if !(lens.squeeze > 0.0 && lens.squeeze.is_finite()) {
+            return Err(CameraError::Squeeze(lens.squeeze));
+        }
+        if !(lens.tilt > -90.0 && lens.tilt < 90.0) {
+            return Err(CameraError::Tilt(lens.tilt));
+        }
+
         // create virtual viewport at the focus distance
+        // an anamorphic lens takes in a wider view, squeezed onto the viewport
         let viewport_height = 2.0 * (vfov.to_radians() / 2.0).tan() * focus_dist;
-        let viewport_width = aspect_ratio * viewport_height;
+        let viewport_width = lens.squeeze * aspect_ratio * viewport_height;
 
-        let look_to = (look_at - look_from).unit();
-        let unit_horizontal = look_to.cross(&vup).unit();
-        let unit_vertical = unit_horizontal.cross(&look_to);
+        let horizontal = viewport_width * view.right;
+        let vertical = viewport_height * view.up;
 
-        let horizontal = viewport_width * unit_horizontal;
-        let vertical = viewport_height * unit_vertical;
+        // the plane of focus turns from facing the viewpoint towards facing
+        // up, through the point in focus straight ahead
+        let focal_plane = (lens.tilt != 0.0).then(|| {
+            let (sin, cos) = lens.tilt.to_radians().sin_cos();
+            (cos * view.forward - sin * view.up, cos * focus_dist)
+        });
 
-        Self {
+        Ok(Self {
             origin: look_from,
             horizontal,
             vertical,
-            lower_left_corner: look_from + focus_dist * look_to - horizontal / 2.0 - vertical / 2.0,
+            lower_left_corner: look_from + focus_dist * view.forward
+                - horizontal / 2.0
+                - vertical / 2.0,
             lens_radius: aperture / 2.0,
+            focal_plane,
+            params,
+        })
+    }
+}
+
+impl From<Perspective> for CameraParams {
+    fn from(camera: Perspective) -> Self {
+        camera.params
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<OrthographicParams> for Orthographic {
+    type Error = CameraError;
+
+    fn try_from(ortho: OrthographicParams) -> Result<Self, CameraError> {
+        CameraBuilder::from(ortho.params).build_orthographic(ortho.height)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Orthographic> for OrthographicParams {
+    fn from(camera: Orthographic) -> Self {
+        Self {
+            params: camera.params,
+            height: camera.height,
         }
     }
+}
 
-    /// Gets the ray passing through a point in the viewport,
-    /// specified by its relative width and height.
-    pub fn get_ray(&self, u: f64, v: f64, rng: &mut impl RngExt) -> Ray {
-        // sample offset vector parallel to the viewport plane
-        let offset = loop {
-            // sample from the unit disc with rejection method
-            let x = rng.float_between(-1.0, 1.0);
-            let y = rng.float_between(-1.0, 1.0);
-            if x * x + y * y < 1.0 {
-                break (x * self.horizontal.unit() + y * self.vertical.unit()) * self.lens_radius;
-            }
+impl TryFrom<CameraParams> for Fisheye {
+    type Error = CameraError;
+
+    fn try_from(params: CameraParams) -> Result<Self, CameraError> {
+        CameraBuilder::from(params).build_fisheye()
+    }
+}
+
+impl From<Fisheye> for CameraParams {
+    fn from(camera: Fisheye) -> Self {
+        camera.params
+    }
+}
+
+impl TryFrom<CameraParams> for Equirectangular {
+    type Error = CameraError;
+
+    fn try_from(params: CameraParams) -> Result<Self, CameraError> {
+        CameraBuilder::from(params).build_equirectangular()
+    }
+}
+
+impl From<Equirectangular> for CameraParams {
+    fn from(camera: Equirectangular) -> Self {
+        camera.params
+    }
+}
+
+/// Builds a camera from the parameters, with their focus distance.
+impl From<CameraParams> for CameraBuilder {
+    fn from(params: CameraParams) -> Self {
+        Self {
+            focus_dist: Some(params.focus_dist),
+            params,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A camera at the origin looking down -Z, focused at 10.
+    fn builder() -> CameraBuilder {
+        CameraBuilder::new(Point3::default(), Point3::new(0.0, 0.0, -1.0))
+            .vfov(60.0)
+            .aspect_ratio(1.5)
+            .aperture(1.0)
+            .focus_dist(10.0)
+    }
+
+    /// The point in focus through a point of the image, where the rays
+    /// from all over the lens meet.
+    fn in_focus(camera: &Perspective, u: f64, v: f64) -> Point3 {
+        let focus = camera.get_ray(u, v, (0.5, 0.5)).unwrap().at(1.0);
+        for lens in [(0.1, 0.2), (0.9, 0.5), (0.3, 0.95)] {
+            let p = camera.get_ray(u, v, lens).unwrap().at(1.0);
+            assert!((p - focus).length() < 1e-9, "{:?} != {:?}", p, focus);
+        }
+
+        focus
+    }
+
+    #[test]
+    fn tilted_focus() {
+        let tilt = 30.0;
+        let camera = builder().tilt(tilt).build().unwrap();
+
+        // the plane through the point in focus ahead, laid back by the tilt
+        let (sin, cos) = f64::to_radians(tilt).sin_cos();
+        let normal = Vec3::new(0.0, -sin, -cos);
+        let center = Point3::new(0.0, 0.0, -10.0);
+        for (u, v) in [(0.5, 0.5), (0.0, 0.0), (1.0, 0.2), (0.3, 1.0)] {
+            let p = in_focus(&camera, u, v);
+            assert!((p - center).dot(&normal).abs() < 1e-9, "{:?}", p);
+        }
+
+        // the focus recedes from the bottom to the top
+        let (bottom, top) = (in_focus(&camera, 0.5, 0.0), in_focus(&camera, 0.5, 1.0));
+        assert!(top.z() < -10.0 && bottom.z() > -10.0);
+
+        // and without a tilt, the plane faces the camera
+        let camera = builder().build().unwrap();
+        assert!((in_focus(&camera, 0.0, 1.0).z() + 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn squeeze_widens_view() {
+        let half_width = |camera: &Perspective| {
+            let d = camera.get_ray(1.0, 0.5, (0.5, 0.5)).unwrap().direction();
+            d.x() / -d.z()
+        };
+        let half_height = |camera: &Perspective| {
+            let d = camera.get_ray(0.5, 1.0, (0.5, 0.5)).unwrap().direction();
+            d.y() / -d.z()
         };
 
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin - offset,
-        )
+        let spherical = builder().build().unwrap();
+        let anamorphic = builder().squeeze(2.0).build().unwrap();
+        assert!((half_width(&anamorphic) - 2.0 * half_width(&spherical)).abs() < 1e-9);
+        assert!((half_height(&anamorphic) - half_height(&spherical)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn default_focus_dist() {
+        let camera = CameraBuilder::new(Point3::default(), Point3::new(3.0, 0.0, -4.0))
+            .build()
+            .unwrap();
+        assert_eq!(camera.params().focus_dist, 5.0);
+
+        let camera = builder().focus_dist(2.5).build().unwrap();
+        assert_eq!(camera.params().focus_dist, 2.5);
+    }
+
+    #[test]
+    fn degenerate_views() {
+        let point = Point3::new(1.0, 2.0, 3.0);
+        let err = |builder: CameraBuilder| builder.build().unwrap_err();
+
+        assert_eq!(
+            err(CameraBuilder::new(point, point)),
+            CameraError::NoDirection
+        );
+        assert_eq!(err(builder().vup(Vec3::default())), CameraError::ParallelUp);
+        assert_eq!(
+            err(builder().vup(Vec3::new(0.0, 0.0, 2.0))),
+            CameraError::ParallelUp
+        );
+
+        // NaN anywhere in the view is caught, not carried into the rays
+        let nan = Point3::new(f64::NAN, 0.0, 0.0);
+        assert_eq!(err(builder().look_from(nan)), CameraError::NoDirection);
+        assert_eq!(err(builder().look_at(nan)), CameraError::NoDirection);
+        assert_eq!(
+            err(builder().vup(Vec3::new(f64::NAN, 1.0, 0.0))),
+            CameraError::ParallelUp
+        );
+
+        // the other projections check the view the same way
+        assert_eq!(
+            CameraBuilder::new(point, point)
+                .build_orthographic(1.0)
+                .unwrap_err(),
+            CameraError::NoDirection
+        );
+        assert_eq!(
+            CameraBuilder::new(point, point)
+                .build_fisheye()
+                .unwrap_err(),
+            CameraError::NoDirection
+        );
+        assert_eq!(
+            builder()
+                .vup(Vec3::default())
+                .build_equirectangular()
+                .unwrap_err(),
+            CameraError::ParallelUp
+        );
+    }
+
+    #[test]
+    fn degenerate_params() {
+        let err = |builder: CameraBuilder| builder.build().unwrap_err();
+
+        for vfov in [0.0, -10.0, 180.0, f64::NAN] {
+            assert!(matches!(
+                err(builder().vfov(vfov)),
+                CameraError::FieldOfView(_)
+            ));
+        }
+        for ratio in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                err(builder().aspect_ratio(ratio)),
+                CameraError::AspectRatio(_)
+            ));
+        }
+        for aperture in [-0.5, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                err(builder().aperture(aperture)),
+                CameraError::Aperture(_)
+            ));
+        }
+        for focus_dist in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                err(builder().focus_dist(focus_dist)),
+                CameraError::FocusDistance(_)
+            ));
+        }
+        for blades in [0, 2] {
+            assert_eq!(
+                err(builder().aperture_shape(Aperture::polygon(blades, 0.0))),
+                CameraError::Blades(blades)
+            );
+        }
+        for squeeze in [0.0, -2.0, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                err(builder().squeeze(squeeze)),
+                CameraError::Squeeze(_)
+            ));
+        }
+        for tilt in [90.0, -90.0, f64::NAN] {
+            assert!(matches!(err(builder().tilt(tilt)), CameraError::Tilt(_)));
+        }
+
+        // looking at the viewpoint leaves no default focus distance either,
+        // but the direction is reported first
+        assert_eq!(
+            err(CameraBuilder::new(Point3::default(), Point3::default())),
+            CameraError::NoDirection
+        );
+    }
+
+    #[test]
+    fn degenerate_projections() {
+        for height in [0.0, -1.0, f64::NAN] {
+            assert!(matches!(
+                builder().build_orthographic(height).unwrap_err(),
+                CameraError::Height(_)
+            ));
+        }
+        assert!(matches!(
+            builder()
+                .aspect_ratio(0.0)
+                .build_orthographic(1.0)
+                .unwrap_err(),
+            CameraError::AspectRatio(_)
+        ));
+
+        // a fisheye sees all around, but no more
+        assert!(builder().vfov(360.0).build_fisheye().is_ok());
+        for vfov in [0.0, 361.0, f64::NAN] {
+            assert!(matches!(
+                builder().vfov(vfov).build_fisheye().unwrap_err(),
+                CameraError::FieldOfView(_)
+            ));
+        }
     }
 }
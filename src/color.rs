@@ -7,10 +7,14 @@
 
 use std::ops;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::Vec3;
 
 /// A RGB color, with values as float numbers.
 #[derive(Default, Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Color(Vec3);
 
 impl Color {
@@ -18,16 +22,51 @@ impl Color {
         Self(Vec3::new(x, y, z))
     }
 
-    /// Converts a color to RGB bytes.
+    pub fn r(&self) -> f64 {
+        self.0.x()
+    }
+
+    pub fn g(&self) -> f64 {
+        self.0.y()
+    }
+
+    pub fn b(&self) -> f64 {
+        self.0.z()
+    }
+
+    /// The relative luminance of a linear color (Rec. 709 weights).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
+    /// Converts a (sum of) linear color to sRGB bytes, clipping
+    /// the values out of range. See `ToneMapper` for a smoother roll-off.
     pub fn to_bytes(self, samples_per_pixel: u32) -> [u8; 3] {
         let scale = 1.0 / samples_per_pixel as f64;
 
-        // NOTE: sqrt is for gamma correction
-        let r = (self.0.x() * scale).sqrt().clamp(0.0, 0.999) * 256.0;
-        let g = (self.0.y() * scale).sqrt().clamp(0.0, 0.999) * 256.0;
-        let b = (self.0.z() * scale).sqrt().clamp(0.0, 0.999) * 256.0;
+        let encode = |v: f64| (srgb_oetf((v * scale).clamp(0.0, 1.0)) * 255.0).round() as u8;
+
+        [encode(self.r()), encode(self.g()), encode(self.b())]
+    }
+}
+
+/// The sRGB opto-electronic transfer function, i.e. the "gamma correction"
+/// from a linear value in [0, 1] to an encoded value in [0, 1].
+pub(crate) fn srgb_oetf(v: f64) -> f64 {
+    if v <= 0.0031308 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
 
-        [r as u8, g as u8, b as u8]
+/// The inverse of `srgb_oetf`, from an encoded value in [0, 1]
+/// back to a linear value in [0, 1], e.g. for the texels of an image.
+pub(crate) fn srgb_eotf(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
     }
 }
 
@@ -0,0 +1,248 @@
+//! Denoising of low-sample renders.
+//!
+//! Both filters here average each pixel with its neighbors, weighted by how
+//! similar they are. Noise makes the colors unreliable to tell edges apart,
+//! so the weights are mainly guided by the normal and albedo AOVs, which are
+//! nearly noise-free since they only depend on the primary rays. Without the
+//! AOVs, the filters fall back to the colors alone and blur more.
+
+use rayon::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Aovs, Color, Framebuffer};
+
+/// How quickly the weight of a neighbor falls off with the difference
+/// of each feature from the center pixel. Smaller values keep more edges,
+/// and infinity ignores the feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EdgeStops {
+    /// For the colors, compared after `c / (1 + c)` so that bright
+    /// fireflies do not dominate.
+    pub color: f64,
+
+    pub normal: f64,
+    pub albedo: f64,
+}
+
+impl EdgeStops {
+    fn validate(&self) {
+        // NaN fails too
+        assert!(
+            self.color > 0.0 && self.normal > 0.0 && self.albedo > 0.0,
+            "edge stops must be positive"
+        );
+    }
+}
+
+impl Default for EdgeStops {
+    fn default() -> Self {
+        Self {
+            color: 0.2,
+            normal: 0.1,
+            albedo: 0.1,
+        }
+    }
+}
+
+/// A denoising filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Denoiser {
+    /// The joint bilateral filter, over a square window of the given radius
+    /// with a Gaussian falloff of `sigma` pixels.
+    Bilateral {
+        radius: u32,
+        sigma: f64,
+        edges: EdgeStops,
+    },
+
+    /// The edge-avoiding à-trous wavelet filter of Dammertz et al. Each
+    /// iteration applies a 5x5 B-spline kernel with its taps twice as far
+    /// apart, so a wide area is covered at a small cost. The color edge
+    /// stop is halved in each iteration, as the noise is reduced.
+    ATrous { iterations: u32, edges: EdgeStops },
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self::atrous(3)
+    }
+}
+
+impl Denoiser {
+    pub fn bilateral(radius: u32) -> Self {
+        Self::Bilateral {
+            radius,
+            sigma: radius.max(1) as f64 / 2.0,
+            edges: EdgeStops::default(),
+        }
+    }
+
+    pub fn atrous(iterations: u32) -> Self {
+        Self::ATrous {
+            iterations,
+            edges: EdgeStops::default(),
+        }
+    }
+
+    /// Sets the edge stops.
+    ///
+    /// Panics if any of them is not positive.
+    pub fn edges(mut self, edges: EdgeStops) -> Self {
+        edges.validate();
+        match &mut self {
+            Self::Bilateral { edges: e, .. } | Self::ATrous { edges: e, .. } => *e = edges,
+        }
+        self
+    }
+
+    /// Denoises the colors of a render, guided by its AOVs if given.
+    ///
+    /// Panics if any edge stop is not positive, which `edges` checks
+    /// but setting the fields or deserializing does not, or if the AOVs
+    /// are not the size of the colors.
+    pub fn apply(&self, color: &Framebuffer, aovs: Option<&Aovs>) -> Framebuffer {
+        match self {
+            Self::Bilateral { edges, .. } | Self::ATrous { edges, .. } => edges.validate(),
+        }
+        if let Some(aovs) = aovs {
+            let size = |fb: &Framebuffer| (fb.width(), fb.height());
+            for (name, aov) in [("normal", &aovs.normal), ("albedo", &aovs.albedo)] {
+                assert_eq!(
+                    size(aov),
+                    size(color),
+                    "the {} AOV is not the size of the colors",
+                    name
+                );
+            }
+        }
+
+        let mut image = Image::new(color, aovs);
+
+        match *self {
+            Self::Bilateral {
+                radius,
+                sigma,
+                edges,
+            } => {
+                let r = radius as i64;
+                let taps: Vec<_> = (-r..=r)
+                    .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+                    .map(|(dx, dy)| {
+                        let d2 = (dx * dx + dy * dy) as f64;
+                        (dx, dy, (-d2 / (2.0 * sigma * sigma)).exp())
+                    })
+                    .collect();
+
+                image.colors = image.filter(&taps, &edges);
+            }
+            Self::ATrous { iterations, edges } => {
+                const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+                for i in 0..iterations {
+                    let step = 1 << i;
+                    let taps: Vec<_> = (0..5)
+                        .flat_map(|j| (0..5).map(move |k| (k, j)))
+                        .map(|(k, j)| {
+                            let (dx, dy) = ((k - 2) * step, (j - 2) * step);
+                            (dx, dy, KERNEL[k as usize] * KERNEL[j as usize])
+                        })
+                        .collect();
+
+                    let edges = EdgeStops {
+                        color: edges.color / step as f64,
+                        ..edges
+                    };
+                    image.colors = image.filter(&taps, &edges);
+                }
+            }
+        }
+
+        let mut fb = Framebuffer::new(image.width, image.height);
+        for (i, &c) in image.colors.iter().enumerate() {
+            let (x, y) = (i as u32 % image.width, i as u32 / image.width);
+            fb.add_sample(x, y, c);
+        }
+
+        fb
+    }
+}
+
+/// The colors and features of an image, in row-major order.
+struct Image {
+    width: u32,
+    height: u32,
+    colors: Vec<Color>,
+    normals: Option<Vec<Color>>,
+    albedos: Option<Vec<Color>>,
+}
+
+impl Image {
+    fn new(color: &Framebuffer, aovs: Option<&Aovs>) -> Self {
+        Self {
+            width: color.width(),
+            height: color.height(),
+            colors: color.colors().collect(),
+            normals: aovs.map(|aovs| aovs.normal.colors().collect()),
+            albedos: aovs.map(|aovs| aovs.albedo.colors().collect()),
+        }
+    }
+
+    /// Filters the colors with the given taps, i.e. offsets and weights.
+    fn filter(&self, taps: &[(i64, i64, f64)], edges: &EdgeStops) -> Vec<Color> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        // negative channels, from the negative lobes of some pixel
+        // filters, are clamped since the curve is singular at -1
+        let compress = |c: Color| {
+            let [r, g, b] = [c.r(), c.g(), c.b()].map(|c| c.max(0.0));
+            Color::new(r / (1.0 + r), g / (1.0 + g), b / (1.0 + b))
+        };
+
+        (0..width * height)
+            .into_par_iter()
+            .map(|p| {
+                let (x, y) = (p % width, p / width);
+                let p = p as usize;
+                let center = compress(self.colors[p]);
+
+                let mut sum = Color::default();
+                let mut total = 0.0;
+                for &(dx, dy, weight) in taps {
+                    let (qx, qy) = (x + dx, y + dy);
+                    if qx < 0 || qx >= width || qy < 0 || qy >= height {
+                        continue;
+                    }
+                    let q = (qy * width + qx) as usize;
+
+                    let mut exponent =
+                        distance2(center, compress(self.colors[q])) / square(edges.color);
+                    if let Some(normals) = &self.normals {
+                        exponent += distance2(normals[p], normals[q]) / square(edges.normal);
+                    }
+                    if let Some(albedos) = &self.albedos {
+                        exponent += distance2(albedos[p], albedos[q]) / square(edges.albedo);
+                    }
+
+                    let weight = weight * (-exponent).exp();
+                    sum += weight * self.colors[q];
+                    total += weight;
+                }
+
+                // the center pixel always has a positive weight
+                (1.0 / total) * sum
+            })
+            .collect()
+    }
+}
+
+fn square(x: f64) -> f64 {
+    x * x
+}
+
+/// The squared distance between two colors, as vectors.
+fn distance2(a: Color, b: Color) -> f64 {
+    square(a.r() - b.r()) + square(a.g() - b.g()) + square(a.b() - b.b())
+}
@@ -0,0 +1,125 @@
+//! Pixel reconstruction filters.
+//!
+//! Each sample is splatted to all pixels around it within the filter radius,
+//! weighted by the filter at the offset from the pixel center. Each pixel is
+//! then the weighted average of the samples it receives. All filters here
+//! are separable, i.e. the product of a 1D filter in each direction.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A pixel reconstruction filter, with its radius in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Filter {
+    /// Weighs all samples within the radius equally. With radius 0.5,
+    /// it is a plain average of the samples inside each pixel.
+    Box { radius: f64 },
+
+    /// Weighs samples linearly falling off from the center.
+    Tent { radius: f64 },
+
+    /// A Gaussian of falloff rate `alpha`, shifted to be 0 at the radius.
+    Gaussian { radius: f64, alpha: f64 },
+
+    /// The Mitchell-Netravali cubic, which has negative lobes so the image
+    /// is sharper, at the cost of slight ringing around edges.
+    Mitchell { radius: f64, b: f64, c: f64 },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    /// Panics if the radius is not positive and finite, and so for
+    /// the other constructors.
+    pub fn tent(radius: f64) -> Self {
+        Self::Tent {
+            radius: checked(radius),
+        }
+    }
+
+    pub fn gaussian(radius: f64) -> Self {
+        Self::Gaussian {
+            radius: checked(radius),
+            alpha: 2.0,
+        }
+    }
+
+    /// The Mitchell filter with the recommended `b = c = 1/3`.
+    pub fn mitchell(radius: f64) -> Self {
+        Self::Mitchell {
+            radius: checked(radius),
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+
+    /// The radius of the filter, in pixels.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. } => radius,
+        }
+    }
+
+    /// Whether the radius is positive and finite, since a filter of no
+    /// radius takes no samples.
+    pub(crate) fn is_valid(&self) -> bool {
+        let radius = self.radius();
+        radius > 0.0 && radius.is_finite()
+    }
+
+    /// The 1D filter at offset `d`, assumed to be within the radius.
+    fn evaluate_1d(&self, d: f64) -> f64 {
+        let d = d.abs();
+
+        match *self {
+            Self::Box { .. } => 1.0,
+            Self::Tent { radius } => (radius - d).max(0.0),
+            Self::Gaussian { radius, alpha } => {
+                ((-alpha * d * d).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Self::Mitchell { radius, b, c } => {
+                // the cubic is defined on [0, 2]
+                let x = 2.0 * d / radius;
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                }
+            }
+        }
+    }
+
+    /// The weight of a sample at offset `(dx, dy)` from the pixel center.
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        let r = self.radius();
+        if dx.abs() > r || dy.abs() > r {
+            return 0.0;
+        }
+
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+}
+
+fn checked(radius: f64) -> f64 {
+    assert!(
+        radius > 0.0 && radius.is_finite(),
+        "filter radius {} is not positive",
+        radius
+    );
+    radius
+}
@@ -0,0 +1,137 @@
+//! Floating-point framebuffers.
+//!
+//! A framebuffer accumulates the linear radiance of samples for each pixel,
+//! without any clamping or quantization, so that the full dynamic range is
+//! kept until the image is written out.
+
+use crate::{Color, Filter};
+
+/// The total weight below which a pixel is empty. Filters with negative
+/// lobes can leave a tiny or negative weight at the edges of the image,
+/// which would blow up or flip the average.
+const MIN_WEIGHT: f64 = 1e-6;
+
+/// The accumulated samples of a pixel.
+#[derive(Default, Debug, Clone, Copy)]
+struct Pixel {
+    /// The weighted sum of the sample colors.
+    sum: Color,
+
+    /// The sum of the sample weights.
+    weight: f64,
+}
+
+/// A framebuffer of linear colors.
+///
+/// Pixels are addressed by `(x, y)` with the origin at the top left corner,
+/// i.e. in the same order as they are stored in most image formats.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Pixel>,
+}
+
+impl Framebuffer {
+    /// Creates an empty (black) framebuffer.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Pixel::default(); (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bound");
+
+        (y * self.width + x) as usize
+    }
+
+    /// Accumulates a sample to a pixel.
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color) {
+        self.add_weighted(x, y, color, 1.0);
+    }
+
+    /// Accumulates a sample to a pixel, with a weight that determines
+    /// how much it contributes to the average.
+    pub fn add_weighted(&mut self, x: u32, y: u32, color: Color, weight: f64) {
+        let i = self.index(x, y);
+        self.pixels[i].sum += weight * color;
+        self.pixels[i].weight += weight;
+    }
+
+    /// Splats a sample at a continuous position to the pixels around it,
+    /// weighted by the filter. Pixel `(x, y)` covers `[x, x + 1)` and
+    /// `[y, y + 1)`, so its center is at `(x + 0.5, y + 0.5)`.
+    ///
+    /// Pixels out of bound are silently skipped.
+    pub fn splat(&mut self, x: f64, y: f64, color: Color, filter: &Filter) {
+        let r = filter.radius();
+
+        // the range of pixels whose center is within the radius
+        let range = |v: f64, len: u32| {
+            let min = (v - r - 0.5).ceil().max(0.0) as u32;
+            let max = (v + r - 0.5).floor().min(len as f64 - 1.0);
+            min..(max + 1.0).max(0.0) as u32
+        };
+
+        for j in range(y, self.height) {
+            for i in range(x, self.width) {
+                let weight = filter.evaluate(i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                if weight != 0.0 {
+                    self.add_weighted(i, j, color, weight);
+                }
+            }
+        }
+    }
+
+    /// Adds all samples of another framebuffer, whose top left corner
+    /// is placed at `(x0, y0)` of this one. Pixels out of bound are skipped.
+    pub fn merge(&mut self, other: &Framebuffer, x0: u32, y0: u32) {
+        for y in 0..other.height.min(self.height.saturating_sub(y0)) {
+            for x in 0..other.width.min(self.width.saturating_sub(x0)) {
+                let src = other.pixels[other.index(x, y)];
+                let i = self.index(x0 + x, y0 + y);
+                self.pixels[i].sum += src.sum;
+                self.pixels[i].weight += src.weight;
+            }
+        }
+    }
+
+    /// The weighted sum and the total weight of the samples of a pixel,
+    /// by its index in row-major order.
+    pub(crate) fn raw(&self, i: usize) -> (Color, f64) {
+        (self.pixels[i].sum, self.pixels[i].weight)
+    }
+
+    /// Overwrites the accumulated samples of a pixel, see `raw`.
+    pub(crate) fn set_raw(&mut self, i: usize, sum: Color, weight: f64) {
+        self.pixels[i] = Pixel { sum, weight };
+    }
+
+    /// The color of a pixel, i.e. the weighted average of its samples,
+    /// or black if it has none, or too little weight.
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        let pixel = self.pixels[self.index(x, y)];
+
+        if pixel.weight > MIN_WEIGHT {
+            (1.0 / pixel.weight) * pixel.sum
+        } else {
+            Color::default()
+        }
+    }
+
+    /// Iterates over the colors of all pixels, row by row from the top.
+    pub fn colors(&self) -> impl Iterator<Item = Color> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| self.get(x, y)))
+    }
+}
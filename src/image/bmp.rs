@@ -0,0 +1,71 @@
+//! Windows Bitmap.
+
+use std::io::{self, Write};
+
+use crate::{Framebuffer, ToneMapper};
+
+/// Writes the framebuffer as a 24-bit uncompressed BMP file.
+pub fn write_bmp(fb: &Framebuffer, tone: &ToneMapper, mut w: impl Write) -> io::Result<()> {
+    const HEADER_SIZE: u32 = 14 + 40;
+
+    // each row is padded to a multiple of 4 bytes
+    let row_size = (3 * fb.width()).div_ceil(4) * 4;
+    let image_size = row_size * fb.height();
+
+    // file header
+    w.write_all(b"BM")?;
+    w.write_all(&(HEADER_SIZE + image_size).to_le_bytes())?;
+    w.write_all(&[0; 4])?; // reserved
+    w.write_all(&HEADER_SIZE.to_le_bytes())?;
+
+    // info header (BITMAPINFOHEADER)
+    w.write_all(&40u32.to_le_bytes())?;
+    w.write_all(&(fb.width() as i32).to_le_bytes())?;
+    w.write_all(&(fb.height() as i32).to_le_bytes())?;
+    w.write_all(&1u16.to_le_bytes())?; // planes
+    w.write_all(&24u16.to_le_bytes())?; // bits per pixel
+    w.write_all(&0u32.to_le_bytes())?; // no compression
+    w.write_all(&image_size.to_le_bytes())?;
+    w.write_all(&2835i32.to_le_bytes())?; // 72 DPI, horizontally
+    w.write_all(&2835i32.to_le_bytes())?; // and vertically
+    w.write_all(&0u32.to_le_bytes())?; // colors in palette
+    w.write_all(&0u32.to_le_bytes())?; // important colors
+
+    // rows go from the bottom to the top, pixels are stored as BGR
+    let padding = vec![0; (row_size - 3 * fb.width()) as usize];
+    for y in (0..fb.height()).rev() {
+        for x in 0..fb.width() {
+            let [r, g, b] = tone.to_bytes(fb.get(x, y));
+            w.write_all(&[b, g, r])?;
+        }
+        w.write_all(&padding)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::two_pixels;
+
+    #[test]
+    fn header_and_padded_row() {
+        let mut out = Vec::new();
+        write_bmp(&two_pixels(), &ToneMapper::default(), &mut out).unwrap();
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            // file header: magic, file size, reserved, offset of the pixels
+            b'B', b'M', 62, 0, 0, 0, 0, 0, 0, 0, 54, 0, 0, 0,
+            // info header: size, width, height, planes, bits per pixel
+            40, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 24, 0,
+            // compression, image size, resolution, palette
+            0, 0, 0, 0, 8, 0, 0, 0, 0x13, 0x0b, 0, 0, 0x13, 0x0b, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            // BGR pixels, padded to 8 bytes
+            0, 0, 255, 255, 0, 0, 0, 0,
+        ];
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,96 @@
+//! OpenEXR.
+//!
+//! Only the simplest flavor is written: a single part scanline image,
+//! with uncompressed 32-bit float RGB channels.
+
+use std::io::{self, Write};
+
+use crate::{Color, Framebuffer};
+
+/// The pixel type of 32-bit floats.
+const FLOAT: i32 = 2;
+
+/// Appends a header attribute.
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// Writes the framebuffer as an OpenEXR file.
+pub fn write_exr(fb: &Framebuffer, mut w: impl Write) -> io::Result<()> {
+    let (width, height) = (fb.width(), fb.height());
+
+    // magic number, then version 2 with no flags (single part scanline)
+    let mut header = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+
+    // channels must be sorted by name
+    let mut channels = vec![];
+    for name in ["B", "G", "R"] {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&FLOAT.to_le_bytes());
+        // linear flag and reserved bytes
+        channels.extend_from_slice(&[0; 4]);
+        // x and y sampling
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+
+    // no compression
+    attribute(&mut header, "compression", "compression", &[0]);
+
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+
+    // increasing y, i.e. from the top
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    w.write_all(&header)?;
+
+    // offset table, each chunk being a single scanline
+    let line_size = 3 * 4 * width as u64;
+    let chunk_size = 8 + line_size;
+    let first_chunk = header.len() as u64 + 8 * height as u64;
+    for y in 0..height as u64 {
+        w.write_all(&(first_chunk + y * chunk_size).to_le_bytes())?;
+    }
+
+    // scanlines, with channels stored one after another
+    for y in 0..height {
+        w.write_all(&(y as i32).to_le_bytes())?;
+        w.write_all(&(line_size as i32).to_le_bytes())?;
+
+        let row: Vec<_> = (0..width).map(|x| fb.get(x, y)).collect();
+        for channel in [Color::b, Color::g, Color::r] {
+            for c in &row {
+                w.write_all(&(channel(c) as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
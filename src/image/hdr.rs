@@ -0,0 +1,87 @@
+//! Radiance HDR (RGBE).
+
+use std::io::{self, Write};
+
+use crate::{Color, Framebuffer};
+
+/// Encodes a color as RGBE, i.e. three mantissas sharing an exponent.
+fn to_rgbe(c: Color) -> [u8; 4] {
+    // the exponent is stored in a byte, so anything too large for it, or
+    // not a number, saturates
+    let saturated = [255; 4];
+    if ![c.r(), c.g(), c.b()].iter().all(|v| v.is_finite()) {
+        return saturated;
+    }
+
+    let max = c.r().max(c.g()).max(c.b());
+    if max < 1e-32 {
+        return [0; 4];
+    }
+
+    // `max = m * 2^e` with `m` in [0.5, 1)
+    let e = max.log2().floor() as i32 + 1;
+    if e > 127 {
+        return saturated;
+    }
+    let e = e.max(-128);
+    let scale = 256.0 / 2f64.powi(e);
+
+    let channel = |v: f64| (v.max(0.0) * scale) as u8;
+
+    [
+        channel(c.r()),
+        channel(c.g()),
+        channel(c.b()),
+        (e + 128) as u8,
+    ]
+}
+
+/// Writes the framebuffer as a Radiance HDR file, with flat
+/// (not run-length encoded) scanlines.
+pub fn write_hdr(fb: &Framebuffer, mut w: impl Write) -> io::Result<()> {
+    write!(w, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n")?;
+    writeln!(w, "-Y {} +X {}", fb.height(), fb.width())?;
+
+    for c in fb.colors() {
+        w.write_all(&to_rgbe(c))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::two_pixels;
+
+    #[test]
+    fn header_and_pixels() {
+        let mut out = Vec::new();
+        write_hdr(&two_pixels(), &mut out).unwrap();
+
+        let mut expected = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        expected.extend([128, 0, 0, 129, 0, 0, 128, 129]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn rgbe() {
+        assert_eq!(to_rgbe(Color::new(0.0, 0.0, 0.0)), [0; 4]);
+        assert_eq!(to_rgbe(Color::new(1.0, 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(to_rgbe(Color::new(0.75, 0.0, 0.0)), [192, 0, 0, 128]);
+    }
+
+    #[test]
+    fn rgbe_saturates() {
+        let max = [255; 4];
+        assert_eq!(to_rgbe(Color::new(f64::INFINITY, 0.0, 0.0)), max);
+        assert_eq!(to_rgbe(Color::new(0.0, f64::NAN, f64::NAN)), max);
+        assert_eq!(to_rgbe(Color::new(0.0, 0.0, 1e39)), max);
+
+        // the largest exponent still fits
+        assert_eq!(
+            to_rgbe(Color::new(2f64.powi(126), 0.0, 0.0)),
+            [128, 0, 0, 255]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Image output.
+//!
+//! Writers take a framebuffer and encode it to any `Write`. The HDR formats
+//! store the linear colors as floats, so no information is lost and
+//! compositing or tone mapping can happen downstream. The other formats
+//! store 8-bit (or 16-bit) sRGB values, after the given tone mapping.
+
+mod bmp;
+mod exr;
+mod hdr;
+mod pfm;
+mod png;
+mod ppm;
+mod tga;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::{Framebuffer, ToneMapper};
+
+pub use self::png::{write_png, write_png16};
+pub use bmp::write_bmp;
+pub use exr::write_exr;
+pub use hdr::write_hdr;
+pub use pfm::write_pfm;
+pub use ppm::{write_plain_ppm, write_ppm};
+pub use tga::write_tga;
+
+/// The supported image formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Png16,
+    Ppm,
+    PlainPpm,
+    Bmp,
+    Tga,
+    Exr,
+    Pfm,
+    Hdr,
+}
+
+impl Format {
+    /// Guesses the format from the extension of a path,
+    /// with the 8-bit and binary variants by default.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "bmp" => Some(Self::Bmp),
+            "tga" => Some(Self::Tga),
+            "exr" => Some(Self::Exr),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            _ => None,
+        }
+    }
+
+    /// Whether the format keeps the linear colors, thus ignores tone mapping.
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, Self::Exr | Self::Pfm | Self::Hdr)
+    }
+
+    /// Writes the framebuffer in this format.
+    pub fn write(&self, fb: &Framebuffer, tone: &ToneMapper, w: impl Write) -> io::Result<()> {
+        match self {
+            Self::Png => write_png(fb, tone, w),
+            Self::Png16 => write_png16(fb, tone, w),
+            Self::Ppm => write_ppm(fb, tone, w),
+            Self::PlainPpm => write_plain_ppm(fb, tone, w),
+            Self::Bmp => write_bmp(fb, tone, w),
+            Self::Tga => write_tga(fb, tone, w),
+            Self::Exr => write_exr(fb, w),
+            Self::Pfm => write_pfm(fb, w),
+            Self::Hdr => write_hdr(fb, w),
+        }
+    }
+}
+
+/// Saves the framebuffer to a file, in the format given by its extension.
+pub fn save(fb: &Framebuffer, tone: &ToneMapper, path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    let format = Format::from_path(path).ok_or_else(|| {
+        let msg = format!("unknown image format: {}", path.display());
+        io::Error::new(io::ErrorKind::InvalidInput, msg)
+    })?;
+
+    save_as(fb, tone, format, path)
+}
+
+/// Saves the framebuffer to a file in the given format,
+/// regardless of its extension.
+pub fn save_as(
+    fb: &Framebuffer,
+    tone: &ToneMapper,
+    format: Format,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    format.write(fb, tone, &mut w)?;
+
+    // flush explicitly, since errors are ignored when dropped
+    w.flush()
+}
+
+/// A red and a blue pixel, side by side.
+#[cfg(test)]
+fn two_pixels() -> Framebuffer {
+    let mut fb = Framebuffer::new(2, 1);
+    fb.add_sample(0, 0, crate::Color::new(1.0, 0.0, 0.0));
+    fb.add_sample(1, 0, crate::Color::new(0.0, 0.0, 1.0));
+    fb
+}
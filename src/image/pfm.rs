@@ -0,0 +1,41 @@
+//! Portable Float Map.
+
+use std::io::{self, Write};
+
+use crate::Framebuffer;
+
+/// Writes the framebuffer as a color PFM file, with 32-bit floats.
+pub fn write_pfm(fb: &Framebuffer, mut w: impl Write) -> io::Result<()> {
+    // a negative scale means little endian
+    write!(w, "PF\n{} {}\n-1.0\n", fb.width(), fb.height())?;
+
+    // scanlines go from the bottom to the top
+    for y in (0..fb.height()).rev() {
+        for x in 0..fb.width() {
+            let c = fb.get(x, y);
+            for v in [c.r(), c.g(), c.b()] {
+                w.write_all(&(v as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::two_pixels;
+
+    #[test]
+    fn header_and_floats() {
+        let mut out = Vec::new();
+        write_pfm(&two_pixels(), &mut out).unwrap();
+
+        let mut expected = b"PF\n2 1\n-1.0\n".to_vec();
+        for v in [1.0f32, 0.0, 0.0, 0.0, 0.0, 1.0] {
+            expected.extend(v.to_le_bytes());
+        }
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,41 @@
+//! Portable Network Graphics.
+
+use std::io::{self, Write};
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::{Framebuffer, ToneMapper};
+
+fn write(fb: &Framebuffer, w: impl Write, depth: BitDepth, data: &[u8]) -> io::Result<()> {
+    let mut encoder = Encoder::new(w, fb.width(), fb.height());
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_depth(depth);
+    // the data is sRGB encoded by the tone mapper
+    encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    // writes the end, whose error would be lost if dropped
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Writes the framebuffer as an 8-bit RGB PNG file.
+pub fn write_png(fb: &Framebuffer, tone: &ToneMapper, w: impl Write) -> io::Result<()> {
+    let data: Vec<_> = fb.colors().flat_map(|c| tone.to_bytes(c)).collect();
+
+    write(fb, w, BitDepth::Eight, &data)
+}
+
+/// Writes the framebuffer as a 16-bit RGB PNG file.
+pub fn write_png16(fb: &Framebuffer, tone: &ToneMapper, w: impl Write) -> io::Result<()> {
+    // PNG stores samples in big endian
+    let data: Vec<_> = fb
+        .colors()
+        .flat_map(|c| tone.to_16bit(c))
+        .flat_map(u16::to_be_bytes)
+        .collect();
+
+    write(fb, w, BitDepth::Sixteen, &data)
+}
@@ -0,0 +1,51 @@
+//! Portable Pixmap.
+
+use std::io::{self, Write};
+
+use crate::{Framebuffer, ToneMapper};
+
+/// Writes the framebuffer as a binary (P6) PPM file.
+pub fn write_ppm(fb: &Framebuffer, tone: &ToneMapper, mut w: impl Write) -> io::Result<()> {
+    write!(w, "P6\n{} {}\n255\n", fb.width(), fb.height())?;
+
+    for c in fb.colors() {
+        w.write_all(&tone.to_bytes(c))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the framebuffer as a plain text (P3) PPM file.
+pub fn write_plain_ppm(fb: &Framebuffer, tone: &ToneMapper, mut w: impl Write) -> io::Result<()> {
+    writeln!(w, "P3\n{} {}\n255", fb.width(), fb.height())?;
+
+    for c in fb.colors() {
+        let [r, g, b] = tone.to_bytes(c);
+        writeln!(w, "{} {} {}", r, g, b)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::two_pixels;
+
+    #[test]
+    fn binary() {
+        let mut out = Vec::new();
+        write_ppm(&two_pixels(), &ToneMapper::default(), &mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff");
+    }
+
+    #[test]
+    fn plain() {
+        let mut out = Vec::new();
+        write_plain_ppm(&two_pixels(), &ToneMapper::default(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "P3\n2 1\n255\n255 0 0\n0 0 255\n"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Truevision TGA.
+
+use std::io::{self, Write};
+
+use crate::{Framebuffer, ToneMapper};
+
+/// Writes the framebuffer as a 24-bit uncompressed TGA file.
+pub fn write_tga(fb: &Framebuffer, tone: &ToneMapper, mut w: impl Write) -> io::Result<()> {
+    let (width, height) = match (u16::try_from(fb.width()), u16::try_from(fb.height())) {
+        (Ok(width), Ok(height)) => (width, height),
+        _ => {
+            let msg = "image too large for TGA";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+    };
+
+    // no image id, no color map, uncompressed true color
+    w.write_all(&[0, 0, 2])?;
+    // color map specification, unused
+    w.write_all(&[0; 5])?;
+    // x and y origin
+    w.write_all(&[0; 4])?;
+    w.write_all(&width.to_le_bytes())?;
+    w.write_all(&height.to_le_bytes())?;
+    // 24 bits per pixel, and the descriptor with a top left origin
+    w.write_all(&[24, 0x20])?;
+
+    // pixels are stored as BGR
+    for c in fb.colors() {
+        let [r, g, b] = tone.to_bytes(c);
+        w.write_all(&[b, g, r])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::two_pixels;
+
+    #[test]
+    fn header_and_pixels() {
+        let mut out = Vec::new();
+        write_tga(&two_pixels(), &ToneMapper::default(), &mut out).unwrap();
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            // id length, color map type, image type, color map specification
+            0, 0, 2, 0, 0, 0, 0, 0,
+            // origin, width, height, bits per pixel, descriptor
+            0, 0, 0, 0, 2, 0, 1, 0, 24, 0x20,
+            // BGR pixels
+            0, 0, 255, 255, 0, 0,
+        ];
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,439 @@
+//! glTF 2.0 import, from `.gltf` (with external or embedded buffers)
+//! and `.glb` files.
+//!
+//! The node hierarchy of the default scene (or the first scene) is
+//! flattened, i.e. meshes, cameras and lights are placed by their global
+//! transforms. Materials are mapped onto the closest ones of ours:
+//!
+//! - The base color (factor times texture) becomes a Lambertian albedo.
+//! - Metals become `Metal` with the base color factor as the albedo and the
+//!   roughness factor as the fuzz, mixed with the diffuse part by the
+//!   metallic factor (times the metallic texture, if any).
+//! - Transmission (`KHR_materials_transmission`) mixes in a dielectric with
+//!   the refractive index of `KHR_materials_ior`, 1.5 by default.
+//! - Normal textures become normal maps, with their scale.
+//! - Emissive materials emit light besides scattering like the above.
+//!
+//! Only the first set of texture coordinates is used, and alpha is ignored.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use ::gltf::camera::Projection;
+use ::gltf::image::{Data, Format};
+use ::gltf::khr_lights_punctual::Kind;
+use ::gltf::mesh::Mode;
+use ::gltf::{Document, Node};
+
+use super::invalid;
+use crate::color::srgb_eotf;
+use crate::object::Object;
+use crate::{
+    Camera, CameraBuilder, CameraError, Color, Material, Mesh, Point3, Sphere, Texture, Vec3,
+    World, PI,
+};
+
+/// A column-major affine transform, like in glTF.
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0.0; 4]; 4];
+    for (col, b_col) in m.iter_mut().zip(b) {
+        for (row, x) in col.iter_mut().enumerate() {
+            *x = (0..4).map(|k| a[k][row] * b_col[k]).sum();
+        }
+    }
+
+    m
+}
+
+fn transform_point(m: &Matrix, p: [f32; 3]) -> Point3 {
+    let v = transform_vector(m, p);
+    Point3::new(v.x() + m[3][0], v.y() + m[3][1], v.z() + m[3][2])
+}
+
+fn transform_vector(m: &Matrix, v: [f32; 3]) -> Vec3 {
+    let [x, y, z] = v.map(f64::from);
+    Vec3::new(
+        m[0][0] * x + m[1][0] * y + m[2][0] * z,
+        m[0][1] * x + m[1][1] * y + m[2][1] * z,
+        m[0][2] * x + m[1][2] * y + m[2][2] * z,
+    )
+}
+
+/// Transforms a normal by the cofactor matrix, which is the inverse
+/// transpose up to a scale, and keeps the same side under mirroring.
+fn transform_normal(m: &Matrix, n: [f32; 3]) -> Vec3 {
+    let col = |i: usize| Vec3::new(m[i][0], m[i][1], m[i][2]);
+    let (c0, c1, c2) = (col(0), col(1), col(2));
+    let [x, y, z] = n.map(f64::from);
+
+    let n = x * c1.cross(&c2) + y * c2.cross(&c0) + z * c0.cross(&c1);
+    if c0.dot(&c1.cross(&c2)) < 0.0 {
+        -n
+    } else {
+        n
+    }
+}
+
+/// The result of importing a glTF file.
+pub struct GltfScene {
+    /// All meshes and lights.
+    pub world: World,
+
+    /// The perspective and orthographic cameras, in the order of the nodes.
+    pub cameras: Vec<Box<dyn Camera>>,
+
+    /// The perspective cameras without an aspect ratio, by their indices,
+    /// which take that of the image.
+    free_aspect: Vec<(usize, CameraBuilder)>,
+}
+
+impl GltfScene {
+    /// Sets the aspect ratio of the image, which the perspective cameras
+    /// without their own aspect ratio take. Until then, it is 16:9.
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) -> Result<(), CameraError> {
+        for (i, builder) in &self.free_aspect {
+            self.cameras[*i] = Box::new(builder.clone().aspect_ratio(aspect_ratio).build()?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads a glTF 2.0 file.
+///
+/// Punctual lights (`KHR_lights_punctual`) have no size, which a path tracer
+/// cannot hit, so they are approximated by small spheres of light, whose
+/// radius is 0.5% of the size of the scene. Point and spot lights have the
+/// same radiant intensity as the light, with its intensity in candela taken
+/// as is, and spot lights shine into their cones with the falloff of glTF.
+/// Directional lights are placed far away and give the same irradiance.
+/// Such small lights converge slowly, so emissive meshes are preferred
+/// as lights.
+pub fn load_gltf(path: impl AsRef<Path>) -> io::Result<GltfScene> {
+    let (doc, buffers, images) = ::gltf::import(path).map_err(|err| match err {
+        ::gltf::Error::Io(err) => err,
+        err => invalid(err.to_string()),
+    })?;
+
+    let mut importer = Importer {
+        buffers: &buffers,
+        images: &images,
+        materials: HashMap::new(),
+        world: World::new(),
+        cameras: Vec::new(),
+        free_aspect: Vec::new(),
+        lights: Vec::new(),
+    };
+
+    let scene = doc.default_scene().or_else(|| doc.scenes().next());
+    for node in scene.iter().flat_map(|scene| scene.nodes()) {
+        importer.visit(&node, &IDENTITY)?;
+    }
+
+    importer.add_lights(&doc);
+
+    Ok(GltfScene {
+        world: importer.world,
+        cameras: importer.cameras,
+        free_aspect: importer.free_aspect,
+    })
+}
+
+/// A punctual light, by its global transform and its index.
+struct Light {
+    transform: Matrix,
+    index: usize,
+}
+
+struct Importer<'a> {
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [Data],
+
+    /// The materials converted so far, by their indices.
+    materials: HashMap<Option<usize>, Material>,
+
+    world: World,
+    cameras: Vec<Box<dyn Camera>>,
+    free_aspect: Vec<(usize, CameraBuilder)>,
+
+    /// The lights are added after the meshes, whose sizes they depend on.
+    lights: Vec<Light>,
+}
+
+impl Importer<'_> {
+    fn visit(&mut self, node: &Node, parent: &Matrix) -> io::Result<()> {
+        let local = node.transform().matrix().map(|col| col.map(f64::from));
+        let transform = multiply(parent, &local);
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.add_primitive(&primitive, &transform)?;
+            }
+        }
+
+        if let Some(camera) = node.camera() {
+            // the camera looks towards -Z, with +Y up
+            let look_from = transform_point(&transform, [0.0; 3]);
+            let forward = transform_vector(&transform, [0.0, 0.0, -1.0]);
+            let vup = transform_vector(&transform, [0.0, 1.0, 0.0]);
+            let builder = CameraBuilder::new(look_from, look_from + forward).vup(vup);
+
+            let built: Result<Box<dyn Camera>, _> = match camera.projection() {
+                Projection::Perspective(p) => {
+                    let builder = builder.vfov(f64::from(p.yfov()).to_degrees());
+                    let builder = match p.aspect_ratio() {
+                        Some(ratio) => builder.aspect_ratio(ratio.into()),
+                        None => {
+                            let i = self.cameras.len();
+                            self.free_aspect.push((i, builder.clone()));
+                            builder
+                        }
+                    };
+                    builder.build().map(|camera| Box::new(camera) as _)
+                }
+                // the magnifications are half the size of the view
+                Projection::Orthographic(o) => builder
+                    .aspect_ratio(f64::from(o.xmag()) / f64::from(o.ymag()))
+                    .build_orthographic(2.0 * f64::from(o.ymag()))
+                    .map(|camera| Box::new(camera) as _),
+            };
+            let camera =
+                built.map_err(|err| invalid(format!("camera {}: {}", camera.index(), err)))?;
+            self.cameras.push(camera);
+        }
+
+        if let Some(light) = node.light() {
+            self.lights.push(Light {
+                transform,
+                index: light.index(),
+            });
+        }
+
+        for child in node.children() {
+            self.visit(&child, &transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_primitive(
+        &mut self,
+        primitive: &::gltf::Primitive,
+        transform: &Matrix,
+    ) -> io::Result<()> {
+        // points and lines have no area to hit
+        if primitive.mode() != Mode::Triangles {
+            return Ok(());
+        }
+
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+        let positions: Vec<_> = match reader.read_positions() {
+            Some(positions) => positions.map(|p| transform_point(transform, p)).collect(),
+            None => return Ok(()),
+        };
+        let indices: Vec<_> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+        if indices.iter().any(|&i| i >= positions.len()) {
+            return Err(invalid("vertex index out of range"));
+        }
+
+        let triangles = indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        let material = self.material(&primitive.material());
+        let mut mesh = Mesh::new(positions, triangles, material);
+
+        if let Some(normals) = reader.read_normals() {
+            mesh = mesh.normals(normals.map(|n| transform_normal(transform, n)).collect());
+        }
+        // flip v, as images start from the top in glTF but from the bottom here
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            let uvs = uvs.into_f32();
+            mesh = mesh.uvs(
+                uvs.map(|[u, v]| (f64::from(u), 1.0 - f64::from(v)))
+                    .collect(),
+            );
+        }
+
+        self.world.add(mesh);
+        Ok(())
+    }
+
+    fn material(&mut self, m: &::gltf::Material) -> Material {
+        if let Some(material) = self.materials.get(&m.index()) {
+            return material.clone();
+        }
+
+        let material = self.convert(m);
+        self.materials.insert(m.index(), material.clone());
+        material
+    }
+
+    fn convert(&self, m: &::gltf::Material) -> Material {
+        let factor = |c: [f32; 3]| Color::new(c[0].into(), c[1].into(), c[2].into());
+
+        let pbr = m.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base = factor([r, g, b]);
+        let albedo = match pbr.base_color_texture() {
+            Some(info) => self.texture(info.texture(), |c| base * srgb(c)),
+            None => Texture::solid(base),
+        };
+
+        let diffuse = Material::lambertian(albedo);
+        let metal = Material::metal(base, pbr.roughness_factor().into());
+        let metallic = f64::from(pbr.metallic_factor());
+
+        // metalness is in the blue channel
+        let mut material = match pbr.metallic_roughness_texture() {
+            Some(info) if metallic > 0.0 => {
+                let mask = self.texture(info.texture(), |c| {
+                    let x = metallic * c[2];
+                    Color::new(x, x, x)
+                });
+                Material::mix_by(diffuse, metal, mask)
+            }
+            _ if metallic <= 0.0 => diffuse,
+            _ if metallic >= 1.0 => metal,
+            _ => Material::mix(diffuse, metal, metallic),
+        };
+
+        if let Some(transmission) = m.transmission() {
+            let weight = f64::from(transmission.transmission_factor());
+            if weight > 0.0 {
+                let glass = Material::dielectric(m.ior().map_or(1.5, f64::from));
+                material = Material::mix(material, glass, weight);
+            }
+        }
+
+        // the scale applies to X and Y in [-1, 1], which are in [0, 1] here
+        if let Some(normal) = m.normal_texture() {
+            let scale = f64::from(normal.scale());
+            let map = self.texture(normal.texture(), |c| {
+                let scaled = |x: f64| (x - 0.5) * scale + 0.5;
+                Color::new(scaled(c[0]), scaled(c[1]), c[2])
+            });
+            material = material.with_normal_map(map);
+        }
+
+        let strength = f64::from(m.emissive_strength().unwrap_or(1.0));
+        let emissive = strength * factor(m.emissive_factor());
+        if emissive != Color::default() {
+            let emit = match m.emissive_texture() {
+                Some(info) => self.texture(info.texture(), |c| emissive * srgb(c)),
+                None => Texture::solid(emissive),
+            };
+            material = material.with_emission(emit);
+        }
+
+        material
+    }
+
+    /// Converts an image to a texture, given how to convert the channels
+    /// of a pixel (in [0, 1], with missing ones as 0) to a color.
+    fn texture(&self, texture: ::gltf::Texture, f: impl Fn([f64; 4]) -> Color) -> Texture {
+        let data = &self.images[texture.source().index()];
+
+        let (channels, size) = match data.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 => (3, 1),
+            Format::R8G8B8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+            Format::R32G32B32FLOAT => (3, 4),
+            Format::R32G32B32A32FLOAT => (4, 4),
+        };
+
+        let channel = |bytes: &[u8]| match *bytes {
+            [x] => f64::from(x) / 255.0,
+            [a, b] => f64::from(u16::from_ne_bytes([a, b])) / 65535.0,
+            [a, b, c, d] => f64::from(f32::from_ne_bytes([a, b, c, d])),
+            _ => unreachable!(),
+        };
+
+        let pixels = data
+            .pixels
+            .chunks_exact(channels * size)
+            .map(|pixel| {
+                let mut c = [0.0; 4];
+                for (x, bytes) in c.iter_mut().zip(pixel.chunks_exact(size)) {
+                    *x = channel(bytes);
+                }
+                f(c)
+            })
+            .collect();
+
+        Texture::image(data.width as usize, data.height as usize, pixels)
+    }
+
+    /// Adds the punctual lights as small spheres of light.
+    fn add_lights(&mut self, doc: &Document) {
+        let lights: Vec<_> = doc.lights().into_iter().flatten().collect();
+
+        let bbox = self.world.bounding_box();
+        let size = (bbox.max - bbox.min).length().max(1.0);
+        let center = bbox.centroid();
+
+        for light in &self.lights {
+            let gltf = &lights[light.index];
+            let [r, g, b] = gltf.color();
+            let intensity = f64::from(gltf.intensity());
+            let color = intensity * Color::new(r.into(), g.into(), b.into());
+
+            let position = transform_point(&light.transform, [0.0; 3]);
+            // lights shine towards -Z
+            let direction = transform_vector(&light.transform, [0.0, 0.0, -1.0]).unit();
+            let (center, radius, radiance) = match gltf.kind() {
+                // a sphere of radiance `L` and radius `r` has the radiant
+                // intensity `L * PI * r^2` in every direction
+                Kind::Point | Kind::Spot { .. } => {
+                    let radius = 0.005 * size;
+                    (position, radius, (1.0 / (PI * radius * radius)) * color)
+                }
+
+                // at distance `d`, the irradiance is `L * PI * r^2 / d^2`
+                Kind::Directional => {
+                    let distance = 100.0 * size;
+                    let radius = 0.005 * distance;
+                    let scale = distance * distance / (PI * radius * radius);
+                    (center - distance * direction, radius, scale * color)
+                }
+            };
+
+            let light = match gltf.kind() {
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => Material::spot_light(
+                    radiance,
+                    direction,
+                    f64::from(inner_cone_angle).to_degrees(),
+                    f64::from(outer_cone_angle).to_degrees(),
+                ),
+                _ => Material::diffuse_light(radiance),
+            };
+            self.world.add(Sphere::new(center, radius, light));
+        }
+    }
+}
+
+/// Decodes the sRGB channels of a pixel to linear values.
+fn srgb(c: [f64; 4]) -> Color {
+    Color::new(srgb_eotf(c[0]), srgb_eotf(c[1]), srgb_eotf(c[2]))
+}
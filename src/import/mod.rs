@@ -0,0 +1,18 @@
+//! Import of scenes and meshes made by other tools.
+
+#[cfg(feature = "gltf")]
+mod gltf;
+mod ply;
+mod stl;
+
+use std::io;
+
+#[cfg(feature = "gltf")]
+pub use self::gltf::{load_gltf, GltfScene};
+pub use ply::{load_ply, read_ply};
+pub use stl::{load_stl, read_stl};
+
+/// An error of malformed data.
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
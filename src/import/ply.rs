@@ -0,0 +1,474 @@
+//! PLY (polygon file format) meshes, in ASCII or binary.
+//!
+//! Vertices are read from the `x`, `y` and `z` properties, with optional
+//! normals `nx`, `ny` and `nz` and colors `red`, `green` and `blue` (taken as
+//! sRGB, in [0, 255] for integers or [0, 1] for floats). Faces are read from
+//! the `vertex_indices` (or `vertex_index`) list, where polygons are split
+//! into triangles as fans. Other elements and properties are skipped.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::{self, SplitAsciiWhitespace};
+
+use super::invalid;
+use crate::color::srgb_eotf;
+use crate::{Color, Material, Mesh, Point3, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+/// The type of a scalar property.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> io::Result<Self> {
+        match name {
+            "char" | "int8" => Ok(Self::I8),
+            "uchar" | "uint8" => Ok(Self::U8),
+            "short" | "int16" => Ok(Self::I16),
+            "ushort" | "uint16" => Ok(Self::U16),
+            "int" | "int32" => Ok(Self::I32),
+            "uint" | "uint32" => Ok(Self::U32),
+            "float" | "float32" => Ok(Self::F32),
+            "double" | "float64" => Ok(Self::F64),
+            _ => Err(invalid(format!("unknown property type {}", name))),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: Scalar,
+
+    /// The type of the length, for a list property.
+    count: Option<Scalar>,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    len: usize,
+    properties: Vec<Property>,
+}
+
+/// The body of the file, from which values are read one by one.
+enum Body<'a> {
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn read(&mut self, kind: Scalar) -> io::Result<f64> {
+        let eof = || invalid("unexpected end of file");
+
+        match self {
+            Self::Ascii(tokens) => {
+                let token = tokens.next().ok_or_else(eof)?;
+                token
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid number {}", token)))
+            }
+
+            Self::Binary { bytes, big_endian } => {
+                let size = kind.size();
+                if bytes.len() < size {
+                    return Err(eof());
+                }
+                let (value, rest) = bytes.split_at(size);
+                *bytes = rest;
+
+                let mut buf = [0; 8];
+                buf[..size].copy_from_slice(value);
+                if *big_endian {
+                    buf[..size].reverse();
+                }
+
+                Ok(match kind {
+                    Scalar::I8 => f64::from(buf[0] as i8),
+                    Scalar::U8 => f64::from(buf[0]),
+                    Scalar::I16 => f64::from(i16::from_le_bytes([buf[0], buf[1]])),
+                    Scalar::U16 => f64::from(u16::from_le_bytes([buf[0], buf[1]])),
+                    Scalar::I32 => f64::from(i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])),
+                    Scalar::U32 => f64::from(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])),
+                    Scalar::F32 => f64::from(f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])),
+                    Scalar::F64 => f64::from_le_bytes(buf),
+                })
+            }
+        }
+    }
+
+    /// Reads the length of a list or a vertex index, which must be
+    /// a non-negative integer.
+    fn read_index(&mut self, kind: Scalar) -> io::Result<usize> {
+        let value = self.read(kind)?;
+        if value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0 {
+            Ok(value as usize)
+        } else {
+            Err(invalid(format!("invalid count or index {}", value)))
+        }
+    }
+}
+
+/// Loads a PLY mesh from a file, see `read_ply`.
+pub fn load_ply(path: impl AsRef<Path>, material: Material) -> io::Result<Mesh> {
+    read_ply(fs::File::open(path)?, material)
+}
+
+/// Reads a PLY mesh with the given material. The vertex colors, if any,
+/// are shown by a material with the texture `Texture::VertexColor`.
+pub fn read_ply(mut r: impl Read, material: Material) -> io::Result<Mesh> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+
+    let (encoding, elements, body) = parse_header(&data)?;
+    let mut body = match encoding {
+        Encoding::Ascii => {
+            let text = str::from_utf8(body).map_err(|_| invalid("invalid ASCII body"))?;
+            Body::Ascii(text.split_ascii_whitespace())
+        }
+        Encoding::LittleEndian => Body::Binary {
+            bytes: body,
+            big_endian: false,
+        },
+        Encoding::BigEndian => Body::Binary {
+            bytes: body,
+            big_endian: true,
+        },
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+    let (mut has_normals, mut has_colors) = (false, false);
+
+    for element in &elements {
+        let find = |name: &str| element.properties.iter().position(|p| p.name == name);
+
+        match element.name.as_str() {
+            "vertex" => {
+                let position = ["x", "y", "z"].map(find);
+                let normal = ["nx", "ny", "nz"].map(find);
+                let color = ["red", "green", "blue"].map(find);
+                if position.contains(&None) {
+                    return Err(invalid("vertices without positions"));
+                }
+                has_normals = !normal.contains(&None);
+                has_colors = !color.contains(&None);
+
+                let mut values = vec![0.0; element.properties.len()];
+                for _ in 0..element.len {
+                    for (value, property) in values.iter_mut().zip(&element.properties) {
+                        *value = read_scalar(&mut body, property)?;
+                    }
+
+                    let get = |i: Option<usize>| i.map_or(0.0, |i| values[i]);
+                    let [x, y, z] = position.map(get);
+                    positions.push(Point3::new(x, y, z));
+
+                    if has_normals {
+                        let [x, y, z] = normal.map(get);
+                        normals.push(Vec3::new(x, y, z));
+                    }
+
+                    if has_colors {
+                        let [r, g, b] = color.map(|i| {
+                            let property = &element.properties[i.unwrap()];
+                            let scale = if property.kind.is_integer() {
+                                255.0
+                            } else {
+                                1.0
+                            };
+                            srgb_eotf(get(i) / scale)
+                        });
+                        colors.push(Color::new(r, g, b));
+                    }
+                }
+            }
+
+            "face" => {
+                let indices = find("vertex_indices")
+                    .or_else(|| find("vertex_index"))
+                    .ok_or_else(|| invalid("faces without vertex indices"))?;
+
+                for _ in 0..element.len {
+                    for (i, property) in element.properties.iter().enumerate() {
+                        let Some(count) = property.count else {
+                            read_scalar(&mut body, property)?;
+                            continue;
+                        };
+
+                        // the length is not trusted for an allocation, since
+                        // the indices run out at the end of the file anyway
+                        let n = body.read_index(count)?;
+                        let mut face = Vec::new();
+                        for _ in 0..n {
+                            face.push(body.read_index(property.kind)?);
+                        }
+
+                        // a fan of triangles around the first vertex
+                        if i == indices {
+                            for k in 1..n.saturating_sub(1) {
+                                triangles.push([face[0], face[k], face[k + 1]]);
+                            }
+                        }
+                    }
+                }
+            }
+
+            _ => {
+                for _ in 0..element.len {
+                    for property in &element.properties {
+                        skip(&mut body, property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    if triangles.iter().flatten().any(|&i| i >= positions.len()) {
+        return Err(invalid("vertex index out of range"));
+    }
+
+    let mut mesh = Mesh::new(positions, triangles, material);
+    if has_normals {
+        mesh = mesh.normals(normals);
+    }
+    if has_colors {
+        mesh = mesh.colors(colors);
+    }
+
+    Ok(mesh)
+}
+
+/// Reads a scalar property, which must not be a list.
+fn read_scalar(body: &mut Body, property: &Property) -> io::Result<f64> {
+    if property.count.is_some() {
+        return Err(invalid(format!("unexpected list {}", property.name)));
+    }
+
+    body.read(property.kind)
+}
+
+fn skip(body: &mut Body, property: &Property) -> io::Result<()> {
+    let n = match property.count {
+        Some(count) => body.read_index(count)?,
+        None => 1,
+    };
+    for _ in 0..n {
+        body.read(property.kind)?;
+    }
+
+    Ok(())
+}
+
+/// Parses the header, and returns the rest of the data as the body.
+fn parse_header(data: &[u8]) -> io::Result<(Encoding, Vec<Element>, &[u8])> {
+    const END: &[u8] = b"end_header";
+
+    let end = data
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| invalid("missing end_header"))?;
+    // the body starts after the line break
+    let body_start = data[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(data.len(), |i| end + i + 1);
+
+    let header = str::from_utf8(&data[..end]).map_err(|_| invalid("invalid header"))?;
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(invalid("not a PLY file"));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    for line in lines {
+        let words: Vec<_> = line.split_ascii_whitespace().collect();
+        match words.as_slice() {
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(invalid(format!("unknown format {}", format))),
+                });
+            }
+
+            ["element", name, len] => elements.push(Element {
+                name: name.to_string(),
+                len: len
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid element count {}", len)))?,
+                properties: Vec::new(),
+            }),
+
+            ["property", rest @ ..] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property before any element"))?;
+                let property = match *rest {
+                    ["list", count, kind, name] => Property {
+                        name: name.to_string(),
+                        kind: Scalar::parse(kind)?,
+                        count: Some(Scalar::parse(count)?),
+                    },
+                    [kind, name] => Property {
+                        name: name.to_string(),
+                        kind: Scalar::parse(kind)?,
+                        count: None,
+                    },
+                    _ => return Err(invalid(format!("invalid property: {}", line))),
+                };
+                element.properties.push(property);
+            }
+
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+
+            _ => return Err(invalid(format!("invalid header line: {}", line))),
+        }
+    }
+
+    let encoding = encoding.ok_or_else(|| invalid("missing format"))?;
+    Ok((encoding, elements, &data[body_start..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Object;
+    use crate::Ray;
+
+    /// A unit square in the XY plane, as a single quad with vertex colors.
+    const SQUARE: &str = "ply
+format ascii 1.0
+comment a colored square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 128 0
+1 0 0 255 128 0
+1 1 0 255 128 0
+0 1 0 255 128 0
+4 0 1 2 3
+";
+
+    fn material() -> Material {
+        Material::lambertian(Color::new(0.5, 0.5, 0.5))
+    }
+
+    /// The header of a binary triangle, with the list count and index types.
+    fn binary_header(count: &str, index: &str) -> Vec<u8> {
+        format!(
+            "ply\nformat binary_little_endian 1.0\nelement vertex 3\n\
+             property float x\nproperty float y\nproperty float z\n\
+             element face 1\nproperty list {} {} vertex_indices\nend_header\n",
+            count, index
+        )
+        .into_bytes()
+    }
+
+    fn binary_vertices(data: &mut Vec<u8>) {
+        for v in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            v.iter().for_each(|c| data.extend(c.to_le_bytes()));
+        }
+    }
+
+    /// Shoots a ray down the Z axis at a point of the XY plane.
+    fn shoot(mesh: &Mesh, x: f64, y: f64) -> Option<Color> {
+        let ray = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        mesh.hit_by(&ray, 0.001, f64::INFINITY)
+            .map(|rec| rec.vertex_color.unwrap_or(Color::new(1.0, 1.0, 1.0)))
+    }
+
+    #[test]
+    fn ascii_with_colors() {
+        let mesh = read_ply(SQUARE.as_bytes(), material()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let color = shoot(&mesh, 0.25, 0.75).unwrap();
+        assert_eq!(color.r(), 1.0);
+        assert!((color.g() - srgb_eotf(128.0 / 255.0)).abs() < 1e-12);
+        assert_eq!(color.b(), 0.0);
+        assert!(shoot(&mesh, 1.5, 0.5).is_none());
+    }
+
+    #[test]
+    fn binary_little_endian() {
+        let mut data = binary_header("uchar", "uint");
+        binary_vertices(&mut data);
+        data.push(3);
+        [0u32, 1, 2]
+            .iter()
+            .for_each(|i| data.extend(i.to_le_bytes()));
+
+        let mesh = read_ply(data.as_slice(), material()).unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+        assert!(shoot(&mesh, 0.25, 0.25).is_some());
+        assert!(shoot(&mesh, 0.75, 0.75).is_none());
+    }
+
+    #[test]
+    fn huge_binary_count() {
+        let mut data = binary_header("uint", "uint");
+        binary_vertices(&mut data);
+        data.extend(u32::MAX.to_le_bytes());
+        [0u32, 1, 2]
+            .iter()
+            .for_each(|i| data.extend(i.to_le_bytes()));
+
+        assert!(read_ply(data.as_slice(), material()).is_err());
+    }
+
+    #[test]
+    fn invalid_ascii_counts_and_indices() {
+        for face in [
+            "1e300 0 1 2",
+            "3.5 0 1 2",
+            "-3 0 1 2",
+            "3 0 -1 2",
+            "3 0 1 4",
+        ] {
+            let src = SQUARE.replace("4 0 1 2 3", face);
+            assert!(read_ply(src.as_bytes(), material()).is_err(), "{}", face);
+        }
+    }
+}
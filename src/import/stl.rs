@@ -0,0 +1,166 @@
+//! STL (stereolithography) meshes, in ASCII or binary.
+//!
+//! STL has no shared vertices, so identical vertices are merged. The facet
+//! normals are ignored in favor of the counterclockwise winding of the
+//! vertices, which they must agree with anyway.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str;
+
+use super::invalid;
+use crate::{Material, Mesh, Point3};
+
+/// Loads a STL mesh from a file, see `read_stl`.
+pub fn load_stl(path: impl AsRef<Path>, material: Material) -> io::Result<Mesh> {
+    read_stl(fs::File::open(path)?, material)
+}
+
+/// Reads a STL mesh with the given material.
+pub fn read_stl(mut r: impl Read, material: Material) -> io::Result<Mesh> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+
+    let mut vertices = Vertices::default();
+    let mut triangles = Vec::new();
+
+    // binary files may also start with "solid", but their size is exact
+    let binary_count = data
+        .get(80..84)
+        .map(|n| u32::from_le_bytes([n[0], n[1], n[2], n[3]]) as usize);
+    let is_binary = binary_count.is_some_and(|n| data.len() == 84 + 50 * n);
+
+    if is_binary {
+        for facet in data[84..].chunks_exact(50) {
+            // skip the normal, and the attribute after the vertices
+            let float = |i: usize| {
+                let b = &facet[12 + 4 * i..16 + 4 * i];
+                f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            };
+            let vertex = |v: usize| Point3::new(float(3 * v), float(3 * v + 1), float(3 * v + 2));
+
+            triangles.push([0, 1, 2].map(|v| vertices.index(vertex(v))));
+        }
+    } else {
+        let text = str::from_utf8(&data).map_err(|_| invalid("invalid ASCII STL"))?;
+        let mut tokens = text.split_ascii_whitespace();
+        if tokens.next() != Some("solid") {
+            return Err(invalid("not a STL file"));
+        }
+
+        let mut facet = Vec::with_capacity(3);
+        while let Some(token) = tokens.next() {
+            match token {
+                "vertex" => {
+                    let mut coord = || -> io::Result<f64> {
+                        let token = tokens
+                            .next()
+                            .ok_or_else(|| invalid("unexpected end of file"))?;
+                        token
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid number {}", token)))
+                    };
+                    let p = Point3::new(coord()?, coord()?, coord()?);
+                    facet.push(vertices.index(p));
+                }
+
+                // a fan of triangles, in case of more than 3 vertices
+                "endloop" => {
+                    for k in 1..facet.len().saturating_sub(1) {
+                        triangles.push([facet[0], facet[k], facet[k + 1]]);
+                    }
+                    facet.clear();
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    Ok(Mesh::new(vertices.positions, triangles, material))
+}
+
+/// The distinct vertices so far, by their bits.
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<Point3>,
+    indices: HashMap<[u64; 3], usize>,
+}
+
+impl Vertices {
+    fn index(&mut self, p: Point3) -> usize {
+        let key = [p.x().to_bits(), p.y().to_bits(), p.z().to_bits()];
+
+        *self.indices.entry(key).or_insert_with(|| {
+            self.positions.push(p);
+            self.positions.len() - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Object;
+    use crate::{Color, Ray, Vec3};
+
+    /// A unit square in the XY plane, as two triangles.
+    const SQUARE: [[[f32; 3]; 3]; 2] = [
+        [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+    ];
+
+    fn material() -> Material {
+        Material::lambertian(Color::new(0.5, 0.5, 0.5))
+    }
+
+    /// Whether a ray down the Z axis hits the mesh at a point of the XY plane.
+    fn hits(mesh: &Mesh, x: f64, y: f64) -> bool {
+        let ray = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        mesh.hit_by(&ray, 0.001, f64::INFINITY).is_some()
+    }
+
+    #[test]
+    fn ascii() {
+        let mut src = String::from("solid square\n");
+        for facet in SQUARE {
+            src += "facet normal 0 0 1\nouter loop\n";
+            for [x, y, z] in facet {
+                src += &format!("vertex {} {} {}\n", x, y, z);
+            }
+            src += "endloop\nendfacet\n";
+        }
+        src += "endsolid square\n";
+
+        let mesh = read_stl(src.as_bytes(), material()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert!(hits(&mesh, 0.75, 0.25) && hits(&mesh, 0.25, 0.75));
+        assert!(!hits(&mesh, 1.5, 0.5));
+    }
+
+    #[test]
+    fn binary() {
+        // a header that starts like an ASCII file
+        let mut data = b"solid square".to_vec();
+        data.resize(80, 0);
+        data.extend(2u32.to_le_bytes());
+        for facet in SQUARE {
+            data.extend([0.0f32, 0.0, 1.0].iter().flat_map(|c| c.to_le_bytes()));
+            data.extend(facet.iter().flatten().flat_map(|c| c.to_le_bytes()));
+            data.extend([0, 0]);
+        }
+
+        let mesh = read_stl(data.as_slice(), material()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert!(hits(&mesh, 0.75, 0.25) && hits(&mesh, 0.25, 0.75));
+    }
+
+    #[test]
+    fn invalid_ascii() {
+        let src = "solid square\nfacet normal 0 0 1\nouter loop\nvertex 0 0 x\n";
+        assert!(read_stl(src.as_bytes(), material()).is_err());
+        assert!(read_stl("not a mesh".as_bytes(), material()).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Instances, i.e. objects moved around without changing their definition.
+//!
+//! Rather than transforming the object, an instance transforms the ray into
+//! the space of the object, then transforms the hit back into the world.
+
+use crate::aabb::Aabb;
+use crate::object::{HitRecord, Object};
+use crate::{Point3, Ray, Vec3};
+
+/// An object translated by an offset.
+#[derive(Debug, Clone)]
+pub struct Translate<T> {
+    object: T,
+    offset: Vec3,
+}
+
+impl<T: Object> Translate<T> {
+    pub fn new(object: T, offset: Vec3) -> Self {
+        Self { object, offset }
+    }
+}
+
+impl<T: Object> Object for Translate<T> {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let moved = Ray::new(ray.origin() - self.offset, ray.direction());
+        let rec = self.object.hit_by(&moved, t_min, t_max)?;
+
+        Some(HitRecord {
+            p: rec.p + self.offset,
+            ..rec
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bbox = self.object.bounding_box();
+        Aabb::new(bbox.min + self.offset, bbox.max + self.offset)
+    }
+}
+
+/// An object rotated around the Y axis.
+#[derive(Debug, Clone)]
+pub struct RotateY<T> {
+    object: T,
+    sin: f64,
+    cos: f64,
+}
+
+impl<T: Object> RotateY<T> {
+    /// Rotates the object by an angle in degrees, counterclockwise
+    /// when looking down from +Y.
+    pub fn new(object: T, angle: f64) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self { object, sin, cos }
+    }
+
+    /// Rotates a vector from the object into the world.
+    fn rotate(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos * v.x() + self.sin * v.z(),
+            v.y(),
+            -self.sin * v.x() + self.cos * v.z(),
+        )
+    }
+
+    /// Rotates a vector from the world into the object.
+    fn unrotate(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos * v.x() - self.sin * v.z(),
+            v.y(),
+            self.sin * v.x() + self.cos * v.z(),
+        )
+    }
+}
+
+impl<T: Object> Object for RotateY<T> {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let origin = Point3::default() + self.unrotate(ray.origin() - Point3::default());
+        let rotated = Ray::new(origin, self.unrotate(ray.direction()));
+        let rec = self.object.hit_by(&rotated, t_min, t_max)?;
+
+        Some(HitRecord {
+            p: Point3::default() + self.rotate(rec.p - Point3::default()),
+            normal: self.rotate(rec.normal),
+            dpdu: self.rotate(rec.dpdu),
+            dpdv: self.rotate(rec.dpdv),
+            ..rec
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object
+            .bounding_box()
+            .corners()
+            .iter()
+            .map(|&corner| {
+                let p = Point3::default() + self.rotate(corner - Point3::default());
+                Aabb::new(p, p)
+            })
+            .reduce(|acc, bbox| acc.union(&bbox))
+            .unwrap()
+    }
+}
@@ -0,0 +1,362 @@
+//! The lens of a perspective camera.
+//!
+//! Beyond the size of the aperture, a lens has the shape of its aperture,
+//! which out-of-focus highlights take (the "bokeh"), an anamorphic squeeze
+//! which widens the view and stretches them vertically, and a tilt of its
+//! plane of focus, as with a tilt-shift lens.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use png::{BitDepth, Decoder, Transformations};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::color::srgb_eotf;
+use crate::sampler::concentric_disk;
+use crate::{Color, PI};
+
+/// The optics of a perspective camera, an ideal round thin lens by default.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Lens {
+    /// The shape of the aperture.
+    pub aperture: Aperture,
+
+    /// How many times an anamorphic lens widens the view horizontally,
+    /// squeezing it onto the viewport, and makes the aperture narrower than
+    /// high. 1 for a spherical lens.
+    ///
+    /// The image is squeezed as recorded on film, and is seen undistorted
+    /// when stretched horizontally by the squeeze, or when the aspect ratio
+    /// of the camera is that of the image divided by the squeeze.
+    pub squeeze: f64,
+
+    /// The tilt of the plane of focus around the horizontal axis of the
+    /// view, in degrees. Positive angles lay it back like the ground ahead,
+    /// so that the focus recedes from the bottom to the top of the image.
+    pub tilt: f64,
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Self {
+            aperture: Aperture::Disk,
+            squeeze: 1.0,
+            tilt: 0.0,
+        }
+    }
+}
+
+impl Lens {
+    /// Maps a sample in [0, 1)² to a point of the aperture, which is in the
+    /// unit disk, or in the square [-1, 1]² for a mask, before the squeeze.
+    pub(crate) fn sample(&self, lens: (f64, f64)) -> (f64, f64) {
+        let (x, y) = self.aperture.sample(lens);
+        (x / self.squeeze, y)
+    }
+}
+
+/// The shape of an aperture, which fits in the unit disk, or in the square
+/// around it for a mask.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Aperture {
+    /// A round opening.
+    #[default]
+    Disk,
+
+    /// A regular polygon, as formed by the straight blades of a diaphragm,
+    /// with a corner at `rotation` degrees counterclockwise from the right.
+    Polygon { blades: u32, rotation: f64 },
+
+    /// An opening by an image, whose brighter pixels let more light through.
+    Mask(ApertureMask),
+}
+
+impl Aperture {
+    pub fn polygon(blades: u32, rotation: f64) -> Self {
+        Self::Polygon { blades, rotation }
+    }
+
+    fn sample(&self, (u, v): (f64, f64)) -> (f64, f64) {
+        match self {
+            Self::Disk => concentric_disk((u, v)),
+            Self::Polygon { blades, rotation } => {
+                // pick the triangle between the center and two adjacent
+                // corners by `u`, whose remainder is stratified as well
+                let n = *blades as f64;
+                let k = (u * n).floor().min(n - 1.0);
+                let u = u * n - k;
+                let corner = |i: f64| {
+                    let phi = rotation.to_radians() + 2.0 * PI * i / n;
+                    (phi.cos(), phi.sin())
+                };
+                let ((ax, ay), (bx, by)) = (corner(k), corner(k + 1.0));
+
+                // uniform in the triangle
+                let s = u.sqrt();
+                (s * ((1.0 - v) * ax + v * bx), s * ((1.0 - v) * ay + v * by))
+            }
+            Self::Mask(mask) => mask.sample(u, v),
+        }
+    }
+}
+
+/// The weights of the pixels of an aperture image, in rows from the top.
+/// The image is centered with its longer side across [-1, 1], so that a
+/// circle touching its sides is as large as the round aperture.
+///
+/// It is (de)serialized by its size and weights.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "MaskWeights", into = "MaskWeights")
+)]
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    weights: Vec<f64>,
+
+    /// The cumulative weights of the rows, and of the pixels in each row,
+    /// both normalized to 1.
+    rows: Vec<f64>,
+    columns: Vec<f64>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct MaskWeights {
+    width: usize,
+    height: usize,
+    weights: Vec<f64>,
+}
+
+impl ApertureMask {
+    /// Creates a mask from the weights of its pixels, in rows from the top.
+    ///
+    /// Panics if there are not `width * height` weights, or they are not
+    /// all finite and non-negative, or they are all 0.
+    pub fn new(width: usize, height: usize, weights: Vec<f64>) -> Self {
+        Self::try_from(MaskWeights {
+            width,
+            height,
+            weights,
+        })
+        .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Loads a mask from a PNG image, weighting each pixel by its luminance
+    /// times its alpha, if any.
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut decoder = Decoder::new(BufReader::new(File::open(path)?));
+        // palettes and bit depths below 8 are expanded to 8 bits
+        decoder.set_transformations(Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let data = &buf[..info.buffer_size()];
+        let values: Vec<f64> = match info.bit_depth {
+            BitDepth::Sixteen => data
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64 / 65535.0)
+                .collect(),
+            _ => data.iter().map(|&b| b as f64 / 255.0).collect(),
+        };
+
+        let weights = values
+            .chunks_exact(info.color_type.samples())
+            .map(|pixel| match *pixel {
+                [gray] => srgb_eotf(gray),
+                [gray, alpha] => srgb_eotf(gray) * alpha,
+                [r, g, b] => Color::new(srgb_eotf(r), srgb_eotf(g), srgb_eotf(b)).luminance(),
+                [r, g, b, alpha] => {
+                    Color::new(srgb_eotf(r), srgb_eotf(g), srgb_eotf(b)).luminance() * alpha
+                }
+                _ => 0.0,
+            })
+            .collect();
+
+        Self::try_from(MaskWeights {
+            width: info.width as usize,
+            height: info.height as usize,
+            weights,
+        })
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn sample(&self, u: f64, v: f64) -> (f64, f64) {
+        // pick a row and then a pixel in it, keeping the remainders
+        // of the samples for the point in the pixel
+        let (row, v) = invert(&self.rows, v);
+        let columns = &self.columns[row * self.width..(row + 1) * self.width];
+        let (column, u) = invert(columns, u);
+
+        let size = self.width.max(self.height) as f64;
+        (
+            (2.0 * (column as f64 + u) - self.width as f64) / size,
+            (self.height as f64 - 2.0 * (row as f64 + v)) / size,
+        )
+    }
+}
+
+/// Finds the bin of a value in [0, 1) by the cumulative weights of the bins,
+/// and where the value is in that bin, also in [0, 1).
+fn invert(cdf: &[f64], x: f64) -> (usize, f64) {
+    let i = cdf.partition_point(|&c| c <= x).min(cdf.len() - 1);
+    let start = if i == 0 { 0.0 } else { cdf[i - 1] };
+    let width = cdf[i] - start;
+
+    if width > 0.0 {
+        (i, ((x - start) / width).clamp(0.0, 1.0))
+    } else {
+        (i, 0.5)
+    }
+}
+
+impl TryFrom<MaskWeights> for ApertureMask {
+    type Error = String;
+
+    fn try_from(mask: MaskWeights) -> Result<Self, String> {
+        let MaskWeights {
+            width,
+            height,
+            weights,
+        } = mask;
+
+        if weights.len() != width * height {
+            return Err(format!(
+                "{} weights for a mask of {}x{}",
+                weights.len(),
+                width,
+                height
+            ));
+        }
+        if let Some(w) = weights.iter().find(|w| !(**w >= 0.0 && w.is_finite())) {
+            return Err(format!("mask weight {} is not a non-negative number", w));
+        }
+
+        let mut rows = Vec::with_capacity(height);
+        let mut columns = Vec::with_capacity(weights.len());
+        let mut total = 0.0;
+        for row in weights.chunks(width.max(1)) {
+            let mut sum = 0.0;
+            for w in row {
+                sum += w;
+                columns.push(sum);
+            }
+            if sum > 0.0 {
+                let start = columns.len() - width;
+                columns[start..].iter_mut().for_each(|c| *c /= sum);
+            }
+
+            total += sum;
+            rows.push(total);
+        }
+        if total <= 0.0 {
+            return Err("the mask lets no light through".to_owned());
+        }
+        rows.iter_mut().for_each(|r| *r /= total);
+
+        Ok(Self {
+            width,
+            height,
+            weights,
+            rows,
+            columns,
+        })
+    }
+}
+
+impl From<ApertureMask> for MaskWeights {
+    fn from(mask: ApertureMask) -> Self {
+        Self {
+            width: mask.width,
+            height: mask.height,
+            weights: mask.weights,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples on a grid over [0, 1)².
+    fn grid() -> impl Iterator<Item = (f64, f64)> {
+        let n = 64;
+        (0..n * n).map(move |i| {
+            let (x, y) = (i % n, i / n);
+            ((x as f64 + 0.5) / n as f64, (y as f64 + 0.5) / n as f64)
+        })
+    }
+
+    #[test]
+    fn polygon_samples_are_inside() {
+        let (blades, rotation) = (6, 15.0);
+        let aperture = Aperture::polygon(blades, rotation);
+        // the apothem, from the center to the middle of an edge
+        let apothem = (PI / blades as f64).cos();
+
+        let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0.0);
+        for (x, y) in grid().map(|s| aperture.sample(s)) {
+            for i in 0..blades {
+                // the normal of the edge between corners `i` and `i + 1`
+                let phi = rotation.to_radians() + 2.0 * PI * (i as f64 + 0.5) / blades as f64;
+                assert!(x * phi.cos() + y * phi.sin() <= apothem + 1e-12);
+            }
+            sum_x += x;
+            sum_y += y;
+            count += 1.0;
+        }
+
+        // evenly spread, so centered
+        assert!((sum_x / count).abs() < 1e-2 && (sum_y / count).abs() < 1e-2);
+    }
+
+    #[test]
+    fn mask_samples_are_in_lit_pixels() {
+        // a 4x2 mask lit at the second pixel of the top row only
+        let mut weights = vec![0.0; 8];
+        weights[1] = 1.0;
+        let mask = ApertureMask::new(4, 2, weights);
+
+        // the longer side spans [-1, 1], so the pixels are 0.5 wide
+        for (x, y) in grid().map(|(u, v)| mask.sample(u, v)) {
+            assert!((-0.5..=0.0).contains(&x), "{}", x);
+            assert!((0.0..=0.5).contains(&y), "{}", y);
+        }
+    }
+
+    #[test]
+    fn mask_weights_are_checked() {
+        let mask = |weights| {
+            ApertureMask::try_from(MaskWeights {
+                width: 2,
+                height: 1,
+                weights,
+            })
+        };
+
+        assert!(mask(vec![1.0]).is_err());
+        assert!(mask(vec![0.0, 0.0]).is_err());
+        assert!(mask(vec![1.0, -1.0]).is_err());
+        assert!(mask(vec![1.0, f64::NAN]).is_err());
+        assert!(mask(vec![1.0, 0.0]).is_ok());
+    }
+
+    #[test]
+    fn squeeze_narrows() {
+        let lens = Lens {
+            squeeze: 2.0,
+            ..Lens::default()
+        };
+        for (x, y) in grid().map(|s| lens.sample(s)) {
+            assert!(x.abs() <= 0.5 + 1e-12 && y.abs() <= 1.0 + 1e-12);
+        }
+    }
+}
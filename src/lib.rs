@@ -1,20 +1,61 @@
+mod aabb;
+mod animation;
+mod bvh;
 mod camera;
 mod color;
+mod denoise;
+mod filter;
+mod framebuffer;
+pub mod image;
+pub mod import;
+mod instance;
+mod lens;
 mod material;
+mod mesh;
 mod object;
+mod perlin;
 mod point3;
+mod progressive;
+mod quad;
 mod random;
 mod ray;
+mod render;
+mod sampler;
+mod scene;
+pub mod scenes;
+mod spectrum;
+mod texture;
+mod tonemap;
 mod vec3;
 
 // re-exports
-pub use camera::Camera;
+pub use animation::{CameraPath, Interpolation};
+pub use camera::{
+    Camera, CameraBuilder, CameraError, CameraParams, Equirectangular, Fisheye, Orthographic,
+    Perspective,
+};
 pub use color::Color;
-pub use material::Material;
-pub use object::{Sphere, World};
+pub use denoise::{Denoiser, EdgeStops};
+pub use filter::Filter;
+pub use framebuffer::Framebuffer;
+pub use instance::{RotateY, Translate};
+pub use lens::{Aperture, ApertureMask, Lens};
+pub use material::{Dispersion, Material};
+pub use mesh::Mesh;
+pub use object::{Background, Sphere, World};
+pub use perlin::Perlin;
 pub use point3::Point3;
+pub use progressive::Progressive;
+pub use quad::{Cuboid, Quad};
 pub use random::RngExt;
 pub use ray::Ray;
+pub use render::{Aovs, Cancel, Frame, Renderer, Tile};
+pub use sampler::{
+    HaltonSampler, IndependentSampler, Sampler, SamplerKind, SobolSampler, StratifiedSampler,
+};
+pub use scene::{Scene, SceneError};
+pub use texture::Texture;
+pub use tonemap::{ToneMap, ToneMapper};
 pub use vec3::Vec3;
 
 pub const INF: f64 = f64::INFINITY;
@@ -0,0 +1,308 @@
+//! The command-line renderer: renders a scene file to an image.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use hooray::image::{self, Format};
+use hooray::{Scene, ToneMap, ToneMapper};
+
+/// Renders a scene file to an image.
+///
+/// The options override the render settings of the scene.
+#[derive(Parser)]
+#[command(name = "hooray", version)]
+struct Args {
+    /// The scene description file, in TOML.
+    scene: PathBuf,
+
+    /// The output image.
+    #[arg(short, long, default_value = "output.png")]
+    output: PathBuf,
+
+    /// The format of the output image, by its extension if not given.
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// The width of the image. If only one of width and height is given,
+    /// the other keeps the aspect ratio of the scene.
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// The height of the image.
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// The number of samples per pixel.
+    #[arg(short, long)]
+    samples: Option<u32>,
+
+    /// The maximum number of bounces of a path.
+    #[arg(short = 'd', long)]
+    max_depth: Option<u32>,
+
+    /// The random seed.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// The number of threads, all cores by default.
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// The tone mapping operator, for formats other than EXR, PFM and HDR.
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Clamp)]
+    tone_map: ToneMapArg,
+
+    /// The exposure adjustment in stops.
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f64,
+
+    /// Renders frames of the camera animation as an image sequence, all of
+    /// them without a range. The output is numbered at its last run of `#`,
+    /// e.g. `frames/####.png`, or else before its extension.
+    #[arg(
+        long,
+        value_name = "START..END",
+        num_args = 0..=1,
+        default_missing_value = "all",
+        value_parser = parse_frames
+    )]
+    frames: Option<Frames>,
+
+    /// Hides the progress bar.
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+/// The frames to render, the end excluded.
+#[derive(Clone)]
+enum Frames {
+    All,
+    Range(Range<u32>),
+}
+
+fn parse_frames(arg: &str) -> Result<Frames, String> {
+    if arg == "all" {
+        return Ok(Frames::All);
+    }
+
+    let (start, end) = arg
+        .split_once("..")
+        .ok_or_else(|| "expected a range like 0..48".to_owned())?;
+    let start = start.parse().map_err(|err| format!("{}: {}", start, err))?;
+    let end = end.parse().map_err(|err| format!("{}: {}", end, err))?;
+    if start >= end {
+        return Err(format!("no frames in {}..{}", start, end));
+    }
+
+    Ok(Frames::Range(start..end))
+}
+
+/// The output of a frame in a sequence, with the last run of `#` in the
+/// file name replaced by the frame number padded to its length, or else
+/// with the number in 4 digits before the extension.
+fn numbered(path: &Path, frame: u32) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let name = match name.rfind('#') {
+        Some(last) => {
+            let start = name[..last].trim_end_matches('#').len();
+            let width = last + 1 - start;
+            format!("{}{:0width$}{}", &name[..start], frame, &name[last + 1..])
+        }
+        None => match name.rfind('.') {
+            Some(dot) if dot > 0 => format!("{}-{:04}{}", &name[..dot], frame, &name[dot..]),
+            _ => format!("{}-{:04}", name, frame),
+        },
+    };
+
+    path.with_file_name(name)
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Png,
+    Png16,
+    Ppm,
+    PlainPpm,
+    Bmp,
+    Tga,
+    Exr,
+    Pfm,
+    Hdr,
+}
+
+impl From<OutputFormat> for Format {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Png => Self::Png,
+            OutputFormat::Png16 => Self::Png16,
+            OutputFormat::Ppm => Self::Ppm,
+            OutputFormat::PlainPpm => Self::PlainPpm,
+            OutputFormat::Bmp => Self::Bmp,
+            OutputFormat::Tga => Self::Tga,
+            OutputFormat::Exr => Self::Exr,
+            OutputFormat::Pfm => Self::Pfm,
+            OutputFormat::Hdr => Self::Hdr,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ToneMapArg {
+    Clamp,
+    Reinhard,
+    Aces,
+    Agx,
+}
+
+impl From<ToneMapArg> for ToneMap {
+    fn from(tone_map: ToneMapArg) -> Self {
+        match tone_map {
+            ToneMapArg::Clamp => Self::Clamp,
+            ToneMapArg::Reinhard => Self::Reinhard,
+            ToneMapArg::Aces => Self::Aces,
+            ToneMapArg::Agx => Self::Agx,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let format = match args.format {
+        Some(format) => Format::from(format),
+        None => Format::from_path(&args.output)
+            .ok_or_else(|| format!("unknown image format of {}", args.output.display()))?,
+    };
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|err| err.to_string())?;
+    }
+
+    let mut scene =
+        Scene::load(&args.scene).map_err(|err| format!("{}: {}", args.scene.display(), err))?;
+
+    // a new size changes the aspect ratio of the camera accordingly
+    let (width, height) = (scene.renderer.width(), scene.renderer.height());
+    let size = match (args.width, args.height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (Some(w), None) => Some((w, (w as f64 * height as f64 / width as f64).round() as u32)),
+        (None, Some(h)) => Some(((h as f64 * width as f64 / height as f64).round() as u32, h)),
+        (None, None) => None,
+    };
+    if let Some((width, height)) = size {
+        if width == 0 || height == 0 {
+            return Err("the image size must be positive".to_owned());
+        }
+
+        scene
+            .set_resolution(width, height)
+            .map_err(|err| err.to_string())?;
+    }
+
+    let mut renderer = scene.renderer.clone();
+
+    if let Some(samples) = args.samples {
+        if samples == 0 {
+            return Err("the samples per pixel must be positive".to_owned());
+        }
+        renderer = renderer.samples_per_pixel(samples);
+    }
+    if let Some(max_depth) = args.max_depth {
+        renderer = renderer.max_depth(max_depth);
+    }
+    if let Some(seed) = args.seed {
+        renderer = renderer.seed(seed);
+    }
+
+    // the frames with their cameras, all built first so that
+    // a degenerate one fails before any rendering
+    let frames = match &args.frames {
+        None => None,
+        Some(frames) => {
+            let all = scene
+                .frames()
+                .ok_or_else(|| "the scene has no camera animation".to_owned())?;
+            let frames = match frames {
+                Frames::All => all,
+                Frames::Range(range) if range.start >= all.start && range.end <= all.end => {
+                    range.clone()
+                }
+                Frames::Range(range) => {
+                    return Err(format!(
+                        "frames {}..{} are not in the animation of frames {}..{}",
+                        range.start, range.end, all.start, all.end
+                    ))
+                }
+            };
+            let cameras = frames
+                .clone()
+                .map(|frame| {
+                    scene
+                        .camera_at(frame)
+                        .map_err(|err| format!("frame {}: {}", frame, err))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(frames.zip(cameras))
+        }
+    };
+
+    let images = frames.as_ref().map_or(1, |frames| frames.len());
+    let bar = if args.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(renderer.width() as u64 * renderer.height() as u64 * images as u64)
+    };
+    bar.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {human_pos:>7}/{human_len:7}",
+        )
+        .unwrap()
+        .progress_chars("##-"),
+    );
+
+    let tone = ToneMapper::new(args.tone_map.into(), args.exposure);
+    let save = |frame: &hooray::Frame, path: &Path| {
+        image::save_as(&frame.color, &tone, format, path)
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    };
+
+    match frames {
+        None => {
+            let frame = renderer.render_with_progress(&scene.world, &*scene.camera, |n| bar.inc(n));
+            save(&frame, &args.output)?;
+        }
+        Some(frames) => {
+            for (n, camera) in frames {
+                let frame =
+                    renderer
+                        .for_frame(n)
+                        .render_with_progress(&scene.world, &*camera, |n| bar.inc(n));
+                save(&frame, &numbered(&args.output, n))?;
+            }
+        }
+    }
+    bar.finish();
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 //! Materials and their behavior when hit.
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::object::HitRecord;
-use crate::{Color, Ray, RngExt, Vec3};
+use crate::{Color, Ray, Sampler, Texture, Vec3, PI};
 
 /// Returns the reflect vector from the surface with given normal.
 ///
@@ -48,6 +51,174 @@ fn reflectance(cosine: f64, ref_ratio: f64) -> f64 {
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
 
+/// The Oren-Nayar rough diffuse reflectance relative to Lambertian,
+/// given the unit vectors towards the viewer (`wi`) and the light (`wo`),
+/// the unit normal, and the roughness `sigma` in radians.
+///
+/// This is the qualitative model of the original paper, see PBRT 9.4.
+fn oren_nayar(wi: Vec3, wo: Vec3, normal: Vec3, sigma: f64) -> f64 {
+    let sigma2 = sigma * sigma;
+    let a = 1.0 - sigma2 / (2.0 * (sigma2 + 0.33));
+    let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+    let cos_i = wi.dot(&normal);
+    let cos_o = wo.dot(&normal);
+
+    // the tangential components, whose dot product is
+    // `sin_i * sin_o * cos(phi_i - phi_o)`, so that dividing it by the
+    // larger cosine gives `cos(phi_i - phi_o) * sin(alpha) * tan(beta)`
+    let tan_i = wi - cos_i * normal;
+    let tan_o = wo - cos_o * normal;
+    let max_cos = cos_i.max(cos_o);
+
+    if max_cos <= 0.0 {
+        return a;
+    }
+
+    a + b * tan_i.dot(&tan_o).max(0.0) / max_cos
+}
+
+/// The sheen BRDF (without the diffuse base) of cloth-like surfaces, given
+/// unit vectors as in `oren_nayar`, and the roughness in (0, 1].
+///
+/// Uses the "Charlie" distribution of Estevez and Kulla (2017), with the
+/// visibility term of Neubelt and Pettineo (2013).
+fn sheen(wi: Vec3, wo: Vec3, normal: Vec3, roughness: f64) -> f64 {
+    let h = wi + wo;
+    if h.near_zero() {
+        return 0.0;
+    }
+
+    let cos_h = h.unit().dot(&normal);
+    let sin_h = (1.0 - cos_h * cos_h).max(0.0).sqrt();
+    let inv_r = 1.0 / roughness;
+    let d = (2.0 + inv_r) * sin_h.powf(inv_r) / (2.0 * PI);
+
+    let cos_i = wi.dot(&normal).max(0.0);
+    let cos_o = wo.dot(&normal).max(0.0);
+    let v = 1.0 / (4.0 * (cos_i + cos_o - cos_i * cos_o)).max(1e-8);
+
+    d * v
+}
+
+/// Returns the ray either reflected or refracted by a smooth boundary
+/// between a dielectric and the air, chosen randomly by the reflectance.
+fn cross_boundary(
+    r_in: &Ray,
+    rec: &HitRecord,
+    refractive_index: f64,
+    sampler: &mut impl Sampler,
+) -> Ray {
+    let ratio = if rec.is_front {
+        1.0 / refractive_index
+    } else {
+        refractive_index
+    };
+
+    let v_in = r_in.direction().unit();
+
+    let dir = match refract(v_in, rec.normal, ratio) {
+        Some(v) => {
+            // can refract theoretically, reflection coefficient test
+            let cos = -v_in.dot(&rec.normal);
+            if sampler.next_1d() > reflectance(cos, ratio) {
+                v // refracts
+            } else {
+                reflect(v_in, rec.normal) // reflects instead
+            }
+        }
+        None => reflect(v_in, rec.normal),
+    };
+
+    Ray::new(rec.p, dir)
+}
+
+/// The step in surface coordinates for finite differences of bump maps.
+const BUMP_DELTA: f64 = 1e-3;
+
+/// Returns a copy of the hit record with its shading normal replaced.
+///
+/// The new normal is given as outward-facing (like the tangent frame),
+/// and is flipped against the incoming ray just like the original one.
+fn with_normal<'a>(rec: &HitRecord<'a>, out_normal: Vec3) -> HitRecord<'a> {
+    let normal = if rec.is_front {
+        out_normal
+    } else {
+        -out_normal
+    };
+
+    HitRecord {
+        normal: normal.unit(),
+        ..*rec
+    }
+}
+
+/// Perturbs the shading normal by a tangent-space normal map,
+/// whose RGB values in [0, 1] encode XYZ components in [-1, 1].
+fn normal_mapped<'a>(rec: &HitRecord<'a>, map: &Texture) -> HitRecord<'a> {
+    let n = if rec.is_front {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+
+    // Gram-Schmidt the tangent against the normal,
+    // then pick the bitangent agreeing with the direction of increasing `v`
+    let t = (rec.dpdu - rec.dpdu.dot(&n) * n).unit();
+    let b = n.cross(&t);
+    let b = if b.dot(&rec.dpdv) < 0.0 { -b } else { b };
+
+    let c = map.value(rec);
+    let (x, y, z) = (2.0 * c.r() - 1.0, 2.0 * c.g() - 1.0, 2.0 * c.b() - 1.0);
+
+    with_normal(rec, x * t + y * b + z * n)
+}
+
+/// Perturbs the shading normal by the gradient of a height map,
+/// with heights taken as the luminance of the texture times `strength`.
+fn bump_mapped<'a>(rec: &HitRecord<'a>, map: &Texture, strength: f64) -> HitRecord<'a> {
+    let height = |rec: &HitRecord| strength * map.value(rec).luminance();
+
+    // shift the hit point along `u` and `v` to take finite differences
+    let shifted_u = HitRecord {
+        p: rec.p + BUMP_DELTA * rec.dpdu,
+        u: rec.u + BUMP_DELTA,
+        ..*rec
+    };
+    let shifted_v = HitRecord {
+        p: rec.p + BUMP_DELTA * rec.dpdv,
+        v: rec.v + BUMP_DELTA,
+        ..*rec
+    };
+
+    let h = height(rec);
+    let dhdu = (height(&shifted_u) - h) / BUMP_DELTA;
+    let dhdv = (height(&shifted_v) - h) / BUMP_DELTA;
+
+    // displace the surface along the normal, and take the new tangents
+    let n = if rec.is_front {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+    let dpdu = rec.dpdu + dhdu * n;
+    let dpdv = rec.dpdv + dhdv * n;
+
+    let bumped = dpdu.cross(&dpdv);
+    // keep it on the same side as the original normal
+    let bumped = if bumped.dot(&n) < 0.0 {
+        -bumped
+    } else {
+        bumped
+    };
+
+    if bumped.near_zero() {
+        *rec
+    } else {
+        with_normal(rec, bumped)
+    }
+}
+
 /// A collection of information when a child ray is scattered.
 pub(crate) struct Scattered {
     /// The color attenuation due to the surface color.
@@ -57,12 +228,79 @@ pub(crate) struct Scattered {
     pub(crate) ray: Ray,
 }
 
+/// A model of how the refractive index varies with the wavelength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Dispersion {
+    /// Cauchy's equation `n = a + b / λ^2`, with λ in micrometers.
+    Cauchy { a: f64, b: f64 },
+
+    /// Sellmeier's equation `n^2 = 1 + Σ b_i λ^2 / (λ^2 - c_i)`,
+    /// with λ in micrometers (thus `c` in square micrometers).
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// The Sellmeier coefficients of the common borosilicate crown glass
+    /// (Schott N-BK7).
+    pub fn bk7() -> Self {
+        Self::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    /// The refractive index at a wavelength in nanometers.
+    pub fn refractive_index(&self, wavelength: f64) -> f64 {
+        let lambda = wavelength / 1000.0;
+        let lambda2 = lambda * lambda;
+
+        match *self {
+            Self::Cauchy { a, b } => a + b / lambda2,
+            Self::Sellmeier { b, c } => {
+                let n2 = 1.0
+                    + (0..3)
+                        .map(|i| b[i] * lambda2 / (lambda2 - c[i]))
+                        .sum::<f64>();
+                n2.sqrt()
+            }
+        }
+    }
+}
+
 /// A material that can scatter incoming rays.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Material {
     /// Surface with Lambertian diffuse, like matte.
     Lambertian {
+        /// The base color of the surface.
+        albedo: Texture,
+    },
+
+    /// Rough diffuse surface, like clay or plaster, which looks flatter
+    /// than Lambertian and appears brighter towards the viewer.
+    OrenNayar {
         /// The base color of the surface.
         albedo: Color,
+
+        /// The roughness of the surface, i.e. the standard deviation of the
+        /// facet angles in radians. With 0 it is the same as Lambertian.
+        sigma: f64,
+    },
+
+    /// Diffuse surface with a retro-reflective sheen at grazing angles,
+    /// like velvet or other cloth.
+    Sheen {
+        /// The base color of the surface.
+        albedo: Color,
+
+        /// The color of the sheen.
+        tint: Color,
+
+        /// The roughness of the fibers in (0, 1]. The larger it is,
+        /// the more spread the sheen is from the silhouette.
+        roughness: f64,
     },
 
     /// Surface like metal that mostly reflects.
@@ -76,12 +314,136 @@ pub enum Material {
     },
 
     /// Material that are transparent and refracts, like glass.
-    Dielectric { refractive_index: f64 },
+    Dielectric {
+        refractive_index: f64,
+
+        /// How the refractive index varies with the wavelength,
+        /// which is only taken into account in spectral mode.
+        dispersion: Option<Dispersion>,
+    },
+
+    /// Translucent material that scatters light beneath its surface,
+    /// like skin, wax or marble.
+    ///
+    /// The object is treated as a smooth dielectric boundary filled with
+    /// a homogeneous medium, and light does a random walk inside it.
+    /// This only makes sense for closed objects.
+    ///
+    /// NOTE: Light may bounce many times inside a dense medium,
+    /// so a larger maximum depth is needed with a high albedo.
+    Subsurface {
+        /// The color attenuation at each scattering event inside.
+        albedo: Color,
+
+        /// The average distance light travels between scattering events.
+        /// The smaller it is, the more opaque the material appears.
+        mean_free_path: f64,
+
+        refractive_index: f64,
+    },
+
+    /// A blend of two materials, e.g. paint over rusty metal.
+    ///
+    /// At each hit, one of the two materials is randomly chosen to scatter
+    /// the ray, which on average gives a linear blend of the two.
+    Mix {
+        first: Box<Material>,
+        second: Box<Material>,
+
+        /// The probability of choosing the second material,
+        /// taken as the luminance of the texture at the hit point.
+        weight: Texture,
+    },
+
+    /// A material whose shading normal is perturbed by a normal map.
+    NormalMapped {
+        base: Box<Material>,
+
+        /// The normal map, in the tangent space given by the surface
+        /// tangents along `u` and `v` and the outward normal.
+        normal_map: Texture,
+    },
+
+    /// A material whose shading normal is perturbed by a bump map.
+    Bumped {
+        base: Box<Material>,
+
+        /// The height map, taken as the luminance of the texture.
+        height_map: Texture,
+
+        /// The scale of the heights in world units.
+        strength: f64,
+    },
+
+    /// Surface that emits light, like a lamp. It absorbs all incoming rays.
+    DiffuseLight {
+        /// The emitted radiance, which may exceed 1.
+        emit: Texture,
+    },
+
+    /// A light which only shines into a cone, like a spotlight, falling off
+    /// smoothly from the inner to the outer angle of the cone. It is meant
+    /// for a small object, whose emission is then nearly from a point.
+    SpotLight {
+        /// The emitted radiance along the axis.
+        emit: Texture,
+
+        /// The axis of the cone.
+        direction: Vec3,
+
+        /// The angles from the axis in degrees, where the falloff
+        /// starts and ends.
+        inner: f64,
+        outer: f64,
+    },
+
+    /// A material which emits light besides scattering, e.g. a glowing
+    /// screen under glass.
+    Emissive {
+        base: Box<Material>,
+
+        /// The emitted radiance, added to that of the base.
+        emit: Texture,
+    },
+
+    /// A homogeneous participating medium, like smoke or fog, filling
+    /// the object whose boundary itself is invisible.
+    ///
+    /// Like `Subsurface`, a ray does a random walk inside the object,
+    /// scattering into a uniformly random direction, so this only makes
+    /// sense for closed objects.
+    Volume {
+        /// The color attenuation at each scattering event.
+        albedo: Texture,
+
+        /// The number of scattering events per unit length on average.
+        /// The larger it is, the thicker the medium appears.
+        density: f64,
+    },
 }
 
 impl Material {
-    pub fn lambertian(albedo: Color) -> Self {
-        Self::Lambertian { albedo }
+    /// A matte material, with either a color or a texture as its albedo.
+    pub fn lambertian(albedo: impl Into<Texture>) -> Self {
+        Self::Lambertian {
+            albedo: albedo.into(),
+        }
+    }
+
+    /// A rough diffuse material, with the roughness `sigma` in degrees.
+    pub fn oren_nayar(albedo: Color, sigma: f64) -> Self {
+        Self::OrenNayar {
+            albedo,
+            sigma: sigma.to_radians(),
+        }
+    }
+
+    pub fn sheen(albedo: Color, tint: Color, roughness: f64) -> Self {
+        Self::Sheen {
+            albedo,
+            tint,
+            roughness: roughness.clamp(1e-3, 1.0), // avoid a singular lobe
+        }
     }
 
     pub fn metal(albedo: Color, fuzz: f64) -> Self {
@@ -92,7 +454,172 @@ impl Material {
     }
 
     pub fn dielectric(refractive_index: f64) -> Self {
-        Self::Dielectric { refractive_index }
+        Self::Dielectric {
+            refractive_index,
+            dispersion: None,
+        }
+    }
+
+    /// A dielectric whose refractive index depends on the wavelength,
+    /// e.g. a glass prism that splits light into a rainbow.
+    ///
+    /// Outside spectral mode, it behaves as an ordinary dielectric with
+    /// the refractive index at the sodium D line (589.3 nm).
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self::Dielectric {
+            refractive_index: dispersion.refractive_index(589.3),
+            dispersion: Some(dispersion),
+        }
+    }
+
+    pub fn subsurface(albedo: Color, mean_free_path: f64, refractive_index: f64) -> Self {
+        Self::Subsurface {
+            albedo,
+            mean_free_path,
+            refractive_index,
+        }
+    }
+
+    /// Blends two materials with a constant weight in [0, 1]:
+    /// 0 gives the first material, 1 gives the second.
+    pub fn mix(first: Material, second: Material, weight: f64) -> Self {
+        let weight = weight.clamp(0.0, 1.0);
+        Self::mix_by(
+            first,
+            second,
+            Texture::solid(Color::new(weight, weight, weight)),
+        )
+    }
+
+    /// Blends two materials with a weight given by a texture mask,
+    /// where black gives the first material and white gives the second.
+    /// The luminance of the mask is clamped to [0, 1].
+    pub fn mix_by(first: Material, second: Material, mask: Texture) -> Self {
+        Self::Mix {
+            first: Box::new(first),
+            second: Box::new(second),
+            weight: mask,
+        }
+    }
+
+    /// Perturbs the shading normal of this material by a tangent-space
+    /// normal map, e.g. as exported by most texturing tools.
+    pub fn with_normal_map(self, normal_map: Texture) -> Self {
+        Self::NormalMapped {
+            base: Box::new(self),
+            normal_map,
+        }
+    }
+
+    /// Perturbs the shading normal of this material by a height map,
+    /// whose luminance is scaled by `strength` into world units.
+    pub fn with_bump_map(self, height_map: Texture, strength: f64) -> Self {
+        Self::Bumped {
+            base: Box::new(self),
+            height_map,
+            strength,
+        }
+    }
+
+    /// A light, with either a color or a texture as its radiance.
+    pub fn diffuse_light(emit: impl Into<Texture>) -> Self {
+        Self::DiffuseLight { emit: emit.into() }
+    }
+
+    /// A light shining into a cone around a direction, with angles
+    /// in degrees, see `Material::SpotLight`.
+    pub fn spot_light(emit: impl Into<Texture>, direction: Vec3, inner: f64, outer: f64) -> Self {
+        Self::SpotLight {
+            emit: emit.into(),
+            direction,
+            inner,
+            outer,
+        }
+    }
+
+    /// Makes this material also emit light.
+    pub fn with_emission(self, emit: impl Into<Texture>) -> Self {
+        Self::Emissive {
+            base: Box::new(self),
+            emit: emit.into(),
+        }
+    }
+
+    pub fn volume(albedo: impl Into<Texture>, density: f64) -> Self {
+        Self::Volume {
+            albedo: albedo.into(),
+            density,
+        }
+    }
+
+    /// The overall color of the surface at the hit point, regardless of
+    /// the lighting, e.g. as a guide for denoising.
+    pub(crate) fn albedo(&self, rec: &HitRecord) -> Color {
+        match *self {
+            Self::Lambertian { ref albedo } | Self::Volume { ref albedo, .. } => albedo.value(rec),
+
+            // lights are shown as they are
+            Self::DiffuseLight { ref emit } | Self::SpotLight { ref emit, .. } => emit.value(rec),
+
+            Self::OrenNayar { albedo, .. }
+            | Self::Sheen { albedo, .. }
+            | Self::Metal { albedo, .. }
+            | Self::Subsurface { albedo, .. } => albedo,
+
+            // transparent
+            Self::Dielectric { .. } => Color::new(1.0, 1.0, 1.0),
+
+            Self::Mix {
+                ref first,
+                ref second,
+                ref weight,
+            } => {
+                let w = weight.value(rec).luminance().clamp(0.0, 1.0);
+                (1.0 - w) * first.albedo(rec) + w * second.albedo(rec)
+            }
+
+            Self::NormalMapped { ref base, .. }
+            | Self::Bumped { ref base, .. }
+            | Self::Emissive { ref base, .. } => base.albedo(rec),
+        }
+    }
+
+    /// The light emitted by the surface at the hit point, towards
+    /// where the incoming ray comes from.
+    pub(crate) fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        match *self {
+            Self::DiffuseLight { ref emit } => emit.value(rec),
+
+            // the falloff of glTF, quadratic in the cosine
+            Self::SpotLight {
+                ref emit,
+                direction,
+                inner,
+                outer,
+            } => {
+                let (cos_inner, cos_outer) = (inner.to_radians().cos(), outer.to_radians().cos());
+                let cos = -r_in.direction().unit().dot(&direction.unit());
+                let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(1e-3)).clamp(0.0, 1.0);
+                t * t * emit.value(rec)
+            }
+
+            Self::Emissive { ref base, ref emit } => emit.value(rec) + base.emitted(r_in, rec),
+
+            Self::Mix {
+                ref first,
+                ref second,
+                ref weight,
+            } => {
+                let w = weight.value(rec).luminance().clamp(0.0, 1.0);
+                (1.0 - w) * first.emitted(r_in, rec) + w * second.emitted(r_in, rec)
+            }
+
+            Self::NormalMapped { ref base, .. } | Self::Bumped { ref base, .. } => {
+                base.emitted(r_in, rec)
+            }
+
+            _ => Color::default(),
+        }
     }
 
     /// Given a record of hit and the incoming ray itself,
@@ -101,27 +628,61 @@ impl Material {
         &self,
         r_in: &Ray,
         rec: &HitRecord,
-        rng: &mut impl RngExt,
+        sampler: &mut impl Sampler,
     ) -> Option<Scattered> {
         match *self {
-            Self::Lambertian { albedo } => {
+            Self::Lambertian { ref albedo } => {
                 // NOTE: there are different ways to sample a scattered ray.
                 // Here we choose the true Lambertian diffusion.
-                let v = rec.normal + rng.unit_vec();
+                let v = rec.normal + sampler.on_unit_sphere();
                 // caution: numeric stability
                 let direction = if v.near_zero() { rec.normal } else { v };
                 let ray = Ray::new(rec.p, direction);
 
                 Some(Scattered {
-                    attenuation: albedo,
+                    attenuation: albedo.value(rec),
                     ray,
                 })
             }
 
+            Self::OrenNayar { albedo, sigma } => {
+                // cosine-weighted sampling like Lambertian, so the attenuation
+                // is just the reflectance relative to it
+                let v = rec.normal + sampler.on_unit_sphere();
+                let direction = if v.near_zero() { rec.normal } else { v };
+
+                let wi = -r_in.direction().unit();
+                let factor = oren_nayar(wi, direction.unit(), rec.normal, sigma);
+
+                Some(Scattered {
+                    attenuation: factor * albedo,
+                    ray: Ray::new(rec.p, direction),
+                })
+            }
+
+            Self::Sheen {
+                albedo,
+                tint,
+                roughness,
+            } => {
+                // cosine-weighted sampling, i.e. with density `cos / PI`,
+                // so the diffuse base needs no extra weight
+                let v = rec.normal + sampler.on_unit_sphere();
+                let direction = if v.near_zero() { rec.normal } else { v };
+
+                let wi = -r_in.direction().unit();
+                let factor = PI * sheen(wi, direction.unit(), rec.normal, roughness);
+
+                Some(Scattered {
+                    attenuation: albedo + factor * tint,
+                    ray: Ray::new(rec.p, direction),
+                })
+            }
+
             Self::Metal { albedo, fuzz } => {
                 let ref_v = reflect(r_in.direction().unit(), rec.normal);
                 // add fuzziness to the reflected direction
-                let direction = ref_v + fuzz * rng.subunit_vec();
+                let direction = ref_v + fuzz * sampler.in_unit_ball();
 
                 // it is possible that after adding fuzziness, the direction went down
                 // under the surface, which means the surface absorbs the ray
@@ -135,33 +696,115 @@ impl Material {
                 }
             }
 
-            Self::Dielectric { refractive_index } => {
+            Self::Dielectric {
+                refractive_index,
+                ref dispersion,
+            } => {
+                let refractive_index = match (dispersion, r_in.wavelength()) {
+                    (Some(dispersion), Some(lambda)) => dispersion.refractive_index(lambda),
+                    _ => refractive_index,
+                };
+
                 // transparent, no reduction of color intensity
                 let attenuation = Color::new(1.0, 1.0, 1.0);
-                let ratio = if rec.is_front {
-                    1.0 / refractive_index
+                let ray = cross_boundary(r_in, rec, refractive_index, sampler);
+
+                Some(Scattered { attenuation, ray })
+            }
+
+            Self::Subsurface {
+                albedo,
+                mean_free_path,
+                refractive_index,
+            } => {
+                // entering the object, only the boundary matters
+                if rec.is_front {
+                    let ray = cross_boundary(r_in, rec, refractive_index, sampler);
+                    return Some(Scattered {
+                        attenuation: Color::new(1.0, 1.0, 1.0),
+                        ray,
+                    });
+                }
+
+                // otherwise the ray has been travelling inside the object,
+                // and may have been scattered by the medium before reaching
+                // the boundary; sample the free flight distance to find out
+                let speed = r_in.direction().length();
+                let flight = -(1.0 - sampler.next_1d()).ln() * mean_free_path;
+
+                if flight < rec.t * speed {
+                    // scattered inside, into a uniformly random direction
+                    let p = r_in.at(flight / speed);
+                    Some(Scattered {
+                        attenuation: albedo,
+                        ray: Ray::new(p, sampler.on_unit_sphere()),
+                    })
                 } else {
-                    refractive_index
-                };
+                    // reached the boundary, try to get out
+                    let ray = cross_boundary(r_in, rec, refractive_index, sampler);
+                    Some(Scattered {
+                        attenuation: Color::new(1.0, 1.0, 1.0),
+                        ray,
+                    })
+                }
+            }
 
-                let v_in = r_in.direction().unit();
-
-                let dir = match refract(v_in, rec.normal, ratio) {
-                    Some(v) => {
-                        // can refract theoretically, reflection coefficient test
-                        let cos = -v_in.dot(&rec.normal);
-                        if rng.float() > reflectance(cos, ratio) {
-                            v // refracts
-                        } else {
-                            reflect(v_in, rec.normal) // reflects instead
-                        }
-                    }
-                    None => reflect(v_in, rec.normal),
+            Self::Mix {
+                ref first,
+                ref second,
+                ref weight,
+            } => {
+                if sampler.next_1d() < weight.value(rec).luminance().clamp(0.0, 1.0) {
+                    second.scatter(r_in, rec, sampler)
+                } else {
+                    first.scatter(r_in, rec, sampler)
+                }
+            }
+
+            Self::NormalMapped {
+                ref base,
+                ref normal_map,
+            } => base.scatter(r_in, &normal_mapped(rec, normal_map), sampler),
+
+            Self::Bumped {
+                ref base,
+                ref height_map,
+                strength,
+            } => base.scatter(r_in, &bump_mapped(rec, height_map, strength), sampler),
+
+            Self::DiffuseLight { .. } | Self::SpotLight { .. } => None,
+
+            Self::Emissive { ref base, .. } => base.scatter(r_in, rec, sampler),
+
+            Self::Volume {
+                ref albedo,
+                density,
+            } => {
+                // the boundary is invisible, the ray just carries on
+                let pass = Scattered {
+                    attenuation: Color::new(1.0, 1.0, 1.0),
+                    ray: Ray::new(rec.p, r_in.direction()),
                 };
 
-                let ray = Ray::new(rec.p, dir);
+                // entering the object
+                if rec.is_front {
+                    return Some(pass);
+                }
+
+                // otherwise the ray has been travelling inside, sample the
+                // free flight distance just like `Subsurface`
+                let speed = r_in.direction().length();
+                let flight = -(1.0 - sampler.next_1d()).ln() / density;
 
-                Some(Scattered { attenuation, ray })
+                if flight < rec.t * speed {
+                    let p = r_in.at(flight / speed);
+                    Some(Scattered {
+                        attenuation: albedo.value(rec),
+                        ray: Ray::new(p, sampler.on_unit_sphere()),
+                    })
+                } else {
+                    Some(pass)
+                }
             }
         }
     }
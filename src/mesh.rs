@@ -0,0 +1,205 @@
+//! Triangle meshes.
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::object::{HitRecord, Object};
+use crate::{Color, Material, Point3, Ray, Vec3};
+
+/// A mesh of triangles sharing their vertices and a material,
+/// e.g. as imported from a modeling tool.
+///
+/// The triangles are kept in their own BVH, so a mesh is a single object
+/// no matter how many triangles it has.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    positions: Vec<Point3>,
+
+    /// The vertex normals, interpolated over each triangle for smooth
+    /// shading. Without them, each triangle is flat.
+    normals: Option<Vec<Vec3>>,
+
+    /// The vertex surface coordinates, for textures.
+    /// Without them, the barycentric coordinates are used instead.
+    uvs: Option<Vec<(f64, f64)>>,
+
+    /// The vertex colors, which `Texture::VertexColor` looks up.
+    colors: Option<Vec<Color>>,
+
+    /// The indices of the vertices of each triangle.
+    triangles: Vec<[usize; 3]>,
+
+    material: Material,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    /// Creates a mesh from the vertex positions and the triangles
+    /// as indices into them.
+    ///
+    /// Panics if any index is out of range.
+    pub fn new(positions: Vec<Point3>, triangles: Vec<[usize; 3]>, material: Material) -> Self {
+        assert!(
+            triangles.iter().flatten().all(|&i| i < positions.len()),
+            "vertex index out of range"
+        );
+
+        let bvh = Bvh::new(triangles.iter().map(|&[a, b, c]| {
+            Aabb::new(positions[a], positions[b]).union(&Aabb::new(positions[c], positions[c]))
+        }));
+
+        Self {
+            positions,
+            normals: None,
+            uvs: None,
+            colors: None,
+            triangles,
+            material,
+            bvh,
+        }
+    }
+
+    /// Sets the vertex normals, which need not be of unit length.
+    ///
+    /// Panics if the number of normals does not match the vertices.
+    pub fn normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(normals.len(), self.positions.len(), "normals mismatch");
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Sets the vertex surface coordinates.
+    ///
+    /// Panics if the number of coordinates does not match the vertices.
+    pub fn uvs(mut self, uvs: Vec<(f64, f64)>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len(), "uvs mismatch");
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Sets the vertex colors, e.g. of a scan.
+    ///
+    /// Panics if the number of colors does not match the vertices.
+    pub fn colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.positions.len(), "colors mismatch");
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Hits a triangle by the Möller-Trumbore algorithm.
+    fn hit_triangle(&self, i: usize, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [i0, i1, i2] = self.triangles[i];
+        let p0 = self.positions[i0];
+        let e1 = self.positions[i1] - p0;
+        let e2 = self.positions[i2] - p0;
+
+        let pvec = ray.direction().cross(&e2);
+        let det = e1.dot(&pvec);
+        // parallel to the triangle, or a degenerate triangle
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        // the barycentric coordinates of the hit point must be in range
+        let tvec = ray.origin() - p0;
+        let b1 = tvec.dot(&pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let qvec = tvec.cross(&e1);
+        let b2 = ray.direction().dot(&qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(&qvec) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let b0 = 1.0 - b1 - b2;
+
+        // the geometric normal is on the same side as the shading normal,
+        // so that the front face agrees with the given normals
+        let geometric = e1.cross(&e2).unit();
+        let out_normal = match &self.normals {
+            Some(normals) => {
+                let n = b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2];
+                if n.near_zero() {
+                    geometric
+                } else {
+                    n.unit()
+                }
+            }
+            None => geometric,
+        };
+        let geometric = if geometric.dot(&out_normal) < 0.0 {
+            -geometric
+        } else {
+            geometric
+        };
+        let is_front = ray.direction().dot(&geometric) < 0.0;
+        let normal = if is_front { out_normal } else { -out_normal };
+
+        // the tangents follow the surface coordinates, if any
+        let (u, v, dpdu, dpdv) = match &self.uvs {
+            Some(uvs) => {
+                let (uv0, uv1, uv2) = (uvs[i0], uvs[i1], uvs[i2]);
+                let u = b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0;
+                let v = b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1;
+
+                let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+                let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+                let det = du1 * dv2 - dv1 * du2;
+                if det.abs() < 1e-12 {
+                    (u, v, e1, e2)
+                } else {
+                    let dpdu = (dv2 * e1 - dv1 * e2) / det;
+                    let dpdv = (du1 * e2 - du2 * e1) / det;
+                    (u, v, dpdu, dpdv)
+                }
+            }
+            None => (b1, b2, e1, e2),
+        };
+
+        let vertex_color = self
+            .colors
+            .as_ref()
+            .map(|colors| b0 * colors[i0] + b1 * colors[i1] + b2 * colors[i2]);
+
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal,
+            is_front,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            vertex_color,
+            material: &self.material,
+            object: 0,
+        })
+    }
+}
+
+impl Object for Mesh {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (rec, _) = self.bvh.hit_by(ray, t_min, t_max, |i, t_max| {
+            self.hit_triangle(i, ray, t_min, t_max)
+        })?;
+
+        Some(rec)
+    }
+
+    /// The box of all triangles, which is empty (at the origin)
+    /// if there are none.
+    fn bounding_box(&self) -> Aabb {
+        self.bvh
+            .bounding_box()
+            .unwrap_or_else(|| Aabb::new(Point3::default(), Point3::default()))
+    }
+}
@@ -1,6 +1,13 @@
 //! Objects and world.
 
-use crate::{Material, Point3, Ray, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use once_cell::sync::OnceCell;
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::{Color, Material, Point3, Ray, Vec3, PI};
 
 /// A collection of information when a ray hits an object.
 ///
@@ -22,9 +29,10 @@ use crate::{Material, Point3, Ray, Vec3};
 ///   while making the API extremely clean and easy.
 ///
 /// A parallel situation exists for the `Scattered` type in `Material`.
+#[derive(Clone, Copy)]
 pub struct HitRecord<'a> {
     /// The travel time of the incoming ray at the hit moment.
-    t: f64,
+    pub(crate) t: f64,
 
     /// The hit point.
     pub(crate) p: Point3,
@@ -38,16 +46,67 @@ pub struct HitRecord<'a> {
     /// Whether the ray hit the object at its front face.
     pub(crate) is_front: bool,
 
+    /// The surface coordinates of the hit point, both in [0, 1].
+    pub(crate) u: f64,
+    pub(crate) v: f64,
+
+    /// The partial derivatives of the hit point with respect to `u` and `v`,
+    /// which together with the outward normal form the tangent frame.
+    pub(crate) dpdu: Vec3,
+    pub(crate) dpdv: Vec3,
+
+    /// The interpolated vertex color of a mesh, if it has any.
+    pub(crate) vertex_color: Option<Color>,
+
     /// The material of the hit object.
     pub(crate) material: &'a Material,
+
+    /// The index of the hit object in the world.
+    pub(crate) object: usize,
 }
 
 /// An object that can be hit by a ray.
 pub trait Object: Sync + Send {
     /// Given an incoming ray and a time interval, returns if there is a hit.
-    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// A box that contains the whole object.
+    fn bounding_box(&self) -> Aabb;
+}
+
+/// Maps a point on the unit sphere to its (u, v) surface coordinates.
+///
+/// `u` goes around the Y axis starting from -X, `v` goes from -Y to +Y.
+fn sphere_uv(p: Vec3) -> (f64, f64) {
+    let theta = (-p.y()).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// The tangents `(dp/du, dp/dv)` of the unit sphere at point `p`,
+/// following the parameterization of `sphere_uv`.
+fn sphere_tangents(p: Vec3) -> (Vec3, Vec3) {
+    let sin_theta = (p.x() * p.x() + p.z() * p.z()).sqrt();
+
+    // at the poles `u` is degenerate, pick any tangent along the equator
+    if sin_theta < 1e-8 {
+        return (Vec3::new(0.0, 0.0, -2.0 * PI), Vec3::new(PI, 0.0, 0.0));
+    }
+
+    let dpdu = 2.0 * PI * Vec3::new(p.z(), 0.0, -p.x());
+    let dpdv = PI
+        * Vec3::new(
+            -p.x() * p.y() / sin_theta,
+            sin_theta,
+            -p.z() * p.y() / sin_theta,
+        );
+
+    (dpdu, dpdv)
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sphere {
     center: Point3,
     radius: f64,
@@ -65,7 +124,7 @@ impl Sphere {
 }
 
 impl Object for Sphere {
-    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let oc = ray.origin() - self.center;
 
         // quadratic equation coefficients
@@ -91,25 +150,75 @@ impl Object for Sphere {
         let is_front = ray.direction().dot(&out_normal) < 0.0;
         // the normal points against the incoming ray
         let normal = if is_front { out_normal } else { -out_normal };
+        let (u, v) = sphere_uv(out_normal);
+        let (dpdu, dpdv) = sphere_tangents(out_normal);
 
         Some(HitRecord {
             t: root,
             p,
             normal,
             is_front,
+            u,
+            v,
+            dpdu: self.radius * dpdu,
+            dpdv: self.radius * dpdv,
             material: &self.material,
+            vertex_color: None,
+            object: 0,
         })
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - r, self.center + r)
+    }
+}
+
+/// What a ray sees when it hits nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Background {
+    /// A gradient from white at the horizon to blue at the zenith,
+    /// which also lights the world.
+    #[default]
+    Sky,
+
+    /// The same color in all directions, e.g. black for a scene
+    /// lit by its lights only.
+    Solid(Color),
+}
+
+impl Background {
+    /// The color seen in a direction.
+    pub(crate) fn color(&self, direction: Vec3) -> Color {
+        match *self {
+            Self::Sky => {
+                // lerp between white and blue according to the y component,
+                // i.e. how high the ray aims
+                let white = Color::new(1.0, 1.0, 1.0);
+                let blue = Color::new(0.5, 0.7, 1.0);
+
+                let t = 0.5 * (direction.unit().y() + 1.0);
+                (1.0 - t) * white + t * blue
+            }
+            Self::Solid(color) => color,
+        }
+    }
 }
 
 /// A world that contains many objects.
+///
+/// A BVH over the objects is built at the first hit,
+/// and rebuilt after any object is added.
 #[derive(Default)]
 pub struct World {
     objects: Vec<Box<dyn Object>>,
+    background: Background,
+    bvh: OnceCell<Bvh>,
 }
 
 impl World {
-    /// Creates an empty world.
+    /// Creates an empty world, with the sky as the background.
     pub fn new() -> Self {
         Self::default()
     }
@@ -117,15 +226,39 @@ impl World {
     /// Add an object to the world.
     pub fn add(&mut self, object: impl Object + 'static) {
         self.objects.push(Box::new(object));
+        self.bvh = OnceCell::new();
+    }
+
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    pub fn background(&self) -> Background {
+        self.background
+    }
+}
+
+impl World {
+    fn bvh(&self) -> &Bvh {
+        self.bvh
+            .get_or_init(|| Bvh::new(self.objects.iter().map(|obj| obj.bounding_box())))
     }
 }
 
 impl Object for World {
-    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        self.objects
-            .iter()
-            .filter_map(|obj| obj.hit_by(ray, t_min, t_max))
-            // get the first hit with smallest `t`
-            .min_by(|x, y| x.t.partial_cmp(&y.t).unwrap())
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (rec, i) = self.bvh().hit_by(ray, t_min, t_max, |i, t_max| {
+            self.objects[i].hit_by(ray, t_min, t_max)
+        })?;
+
+        Some(HitRecord { object: i, ..rec })
+    }
+
+    /// The box of all objects, which is empty (at the origin)
+    /// if there are none.
+    fn bounding_box(&self) -> Aabb {
+        self.bvh()
+            .bounding_box()
+            .unwrap_or_else(|| Aabb::new(Point3::default(), Point3::default()))
     }
 }
@@ -0,0 +1,109 @@
+//! Perlin noise, for procedural textures like marble.
+
+use rand::seq::SliceRandom;
+use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Point3, RngExt, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// A Perlin noise generator, with random gradients at the lattice points.
+///
+/// It is fully determined by its seed, which is all that gets serialized.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "u64", into = "u64")
+)]
+pub struct Perlin {
+    seed: u64,
+    gradients: Vec<Vec3>,
+    perm: [Vec<usize>; 3],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let gradients = (0..POINT_COUNT).map(|_| rng.unit_vec()).collect();
+        let mut perm = || {
+            let mut p: Vec<_> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        let perm = [perm(), perm(), perm()];
+
+        Self {
+            seed,
+            gradients,
+            perm,
+        }
+    }
+
+    /// The noise at a point, in [-1, 1].
+    pub fn noise(&self, p: Point3) -> f64 {
+        let f = |x: f64| x - x.floor();
+        let (u, v, w) = (f(p.x()), f(p.y()), f(p.z()));
+        let (i, j, k) = (
+            p.x().floor() as i64,
+            p.y().floor() as i64,
+            p.z().floor() as i64,
+        );
+
+        // Hermite smoothing
+        let s = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (uu, vv, ww) = (s(u), s(v), s(w));
+
+        let index = |n: i64, axis: usize| self.perm[axis][(n & (POINT_COUNT as i64 - 1)) as usize];
+
+        // trilinear interpolation of the dot products with the gradients
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let g = self.gradients[index(i + di, 0) ^ index(j + dj, 1) ^ index(k + dk, 2)];
+                    let (a, b, c) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vec3::new(u - a, v - b, w - c);
+
+                    accum += (a * uu + (1.0 - a) * (1.0 - uu))
+                        * (b * vv + (1.0 - b) * (1.0 - vv))
+                        * (c * ww + (1.0 - c) * (1.0 - ww))
+                        * g.dot(&weight);
+                }
+            }
+        }
+
+        accum
+    }
+
+    /// The sum of noise at increasing frequencies and decreasing weights.
+    pub fn turbulence(&self, p: Point3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut p = p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(p);
+            weight *= 0.5;
+            p = Point3::new(2.0 * p.x(), 2.0 * p.y(), 2.0 * p.z());
+        }
+
+        accum.abs()
+    }
+}
+
+impl From<u64> for Perlin {
+    fn from(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl From<Perlin> for u64 {
+    fn from(perlin: Perlin) -> Self {
+        perlin.seed
+    }
+}
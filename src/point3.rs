@@ -8,22 +8,47 @@
 //! - Adding a vector to a point yields a point (commutatively)
 //! - Subtracting a vector from a point yields a point
 //! - Subtracting a point from a point yields a vector
+//! - Access of coordinates by index
 //!
 //! As an implementation detail, we use the NewType pattern and delegates its
 //! operator overloading methods to ensure algebraic safety.
 
 use std::ops;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use crate::Vec3;
 
 /// A point in 3D space.
 #[derive(Default, Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Point3(Vec3);
 
 impl Point3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         Self(Vec3::new(x, y, z))
     }
+
+    pub fn x(&self) -> f64 {
+        self.0.x()
+    }
+
+    pub fn y(&self) -> f64 {
+        self.0.y()
+    }
+
+    pub fn z(&self) -> f64 {
+        self.0.z()
+    }
+}
+
+impl ops::Index<usize> for Point3 {
+    type Output = f64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
 }
 
 impl ops::Add<Vec3> for Point3 {
@@ -0,0 +1,240 @@
+//! Progressive rendering with checkpoints.
+//!
+//! A progressive render takes the samples of each pixel in passes and
+//! accumulates them into a float buffer, so a preview is available after
+//! every pass. Its state can be written to a checkpoint file and resumed
+//! later, e.g. after the process is interrupted.
+//!
+//! Since the samplers are stateless (see `crate::sampler`), the state of the
+//! random streams is just the number of samples taken by each pixel. Resuming
+//! thus gives the same image as an uninterrupted run, bit for bit, as long as
+//! the world, the camera and the samples per pass are the same.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::render::Stats;
+use crate::{Camera, Cancel, Color, Frame, Framebuffer, Renderer, Tile, World};
+
+/// The magic bytes at the start of a checkpoint file.
+const MAGIC: &[u8; 8] = b"HOORAYCK";
+
+/// The version of the checkpoint format.
+const VERSION: u32 = 2;
+
+/// A render that takes its samples in passes, and can be saved and resumed.
+///
+/// The renderer is used without AOVs, since they are cheap to render
+/// separately and do not benefit from more passes.
+pub struct Progressive {
+    renderer: Renderer,
+
+    /// The number of samples per pixel taken so far.
+    samples: u32,
+
+    /// The accumulated samples.
+    color: Framebuffer,
+
+    /// The statistics of each pixel, in row-major order.
+    stats: Vec<Stats>,
+}
+
+impl Progressive {
+    /// Starts a progressive render with the given settings.
+    pub fn new(renderer: Renderer) -> Self {
+        let renderer = renderer.aovs(false);
+        let (width, height) = (renderer.width(), renderer.height());
+
+        Self {
+            renderer,
+            samples: 0,
+            color: Framebuffer::new(width, height),
+            stats: vec![Stats::default(); (width * height) as usize],
+        }
+    }
+
+    /// Resumes a render from a checkpoint file.
+    ///
+    /// Fails if the file is not a checkpoint, or if it was made with different
+    /// settings. The world and the camera are not recorded, so it is up to the
+    /// caller to keep them the same.
+    pub fn resume(renderer: Renderer, path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(renderer, BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a checkpoint, see `resume`.
+    pub fn read(renderer: Renderer, mut r: impl Read) -> io::Result<Self> {
+        let mut progressive = Self::new(renderer);
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(&mut r)? != VERSION {
+            return Err(invalid_data("not a checkpoint of a supported version"));
+        }
+
+        // the length is not trusted for an allocation, since it must be
+        // that of the settings anyway
+        let expected = progressive.renderer.image_settings();
+        if read_u32(&mut r)? as usize != expected.len() {
+            return Err(invalid_data("checkpoint made with different settings"));
+        }
+        let mut settings = vec![0; expected.len()];
+        r.read_exact(&mut settings)?;
+        if settings != expected {
+            return Err(invalid_data("checkpoint made with different settings"));
+        }
+
+        progressive.samples = read_u32(&mut r)?;
+        for (i, stats) in progressive.stats.iter_mut().enumerate() {
+            let sum = Color::new(read_f64(&mut r)?, read_f64(&mut r)?, read_f64(&mut r)?);
+            let weight = read_f64(&mut r)?;
+            progressive.color.set_raw(i, sum, weight);
+
+            stats.n = read_u32(&mut r)?;
+            stats.mean = read_f64(&mut r)?;
+            stats.m2 = read_f64(&mut r)?;
+        }
+
+        Ok(progressive)
+    }
+
+    /// Writes a checkpoint to a file.
+    ///
+    /// The file is replaced atomically (where the platform allows), so an
+    /// interruption while saving leaves the previous checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        let mut w = BufWriter::new(File::create(&tmp)?);
+        self.write(&mut w)?;
+        w.into_inner()?.sync_all()?;
+
+        fs::rename(tmp, path)
+    }
+
+    /// Writes a checkpoint, see `save`.
+    ///
+    /// All numbers are little-endian, and floats are stored exactly.
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        let settings = self.renderer.image_settings();
+
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(settings.len() as u32).to_le_bytes())?;
+        w.write_all(&settings)?;
+
+        w.write_all(&self.samples.to_le_bytes())?;
+        for (i, stats) in self.stats.iter().enumerate() {
+            let (sum, weight) = self.color.raw(i);
+            for v in [sum.r(), sum.g(), sum.b(), weight] {
+                w.write_all(&v.to_le_bytes())?;
+            }
+
+            w.write_all(&stats.n.to_le_bytes())?;
+            w.write_all(&stats.mean.to_le_bytes())?;
+            w.write_all(&stats.m2.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// The number of samples per pixel taken so far.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Whether all samples per pixel of the renderer are taken.
+    pub fn is_done(&self) -> bool {
+        self.samples >= self.renderer.max_samples()
+    }
+
+    /// Takes up to `samples` more samples per pixel, calling `on_tile` as
+    /// each tile is finished, see `Renderer::render_tiles`. The tiles only
+    /// have the samples of this pass, while `color` has all of them.
+    ///
+    /// If cancelled, the pass is discarded and `false` is returned.
+    ///
+    /// Panics if `samples` is 0, since the render would never be done.
+    pub fn render_pass(
+        &mut self,
+        world: &World,
+        camera: &dyn Camera,
+        samples: u32,
+        on_tile: impl Fn(&Tile) + Sync,
+        cancel: &Cancel,
+    ) -> bool {
+        assert!(samples > 0, "a pass must take at least one sample");
+
+        let end = (self.samples + samples).min(self.renderer.max_samples());
+        let stats = &mut self.stats;
+
+        match self
+            .renderer
+            .render_samples(world, camera, stats, end, on_tile, cancel)
+        {
+            Some((color, _)) => {
+                self.color.merge(&color, 0, 0);
+                self.samples = end;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Renders in passes of `samples_per_pass` until done or cancelled,
+    /// saving a checkpoint to `path` after each pass.
+    ///
+    /// Panics if `samples_per_pass` is 0.
+    pub fn render_to(
+        &mut self,
+        world: &World,
+        camera: &dyn Camera,
+        samples_per_pass: u32,
+        path: impl AsRef<Path>,
+        on_tile: impl Fn(&Tile) + Sync,
+        cancel: &Cancel,
+    ) -> io::Result<()> {
+        while !self.is_done() {
+            if !self.render_pass(world, camera, samples_per_pass, &on_tile, cancel) {
+                break;
+            }
+            self.save(path.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// The image accumulated so far.
+    pub fn color(&self) -> &Framebuffer {
+        &self.color
+    }
+
+    /// The image accumulated so far, along with the sample heatmap
+    /// if sampling is adaptive.
+    pub fn frame(&self) -> Frame {
+        Frame {
+            color: self.color.clone(),
+            aovs: None,
+            samples: self.renderer.sample_counts(&self.stats),
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_f64(r: &mut impl Read) -> io::Result<f64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
@@ -0,0 +1,133 @@
+//! Flat and boxy objects: parallelograms and boxes made of them.
+
+use crate::aabb::Aabb;
+use crate::object::{HitRecord, Object};
+use crate::{Material, Point3, Ray, Vec3};
+
+/// A parallelogram with a corner `q` and two edges `u` and `v`
+/// from that corner, e.g. a wall or a rectangular light.
+///
+/// Its front face is the one towards `u × v`.
+#[derive(Debug, Clone)]
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    material: Material,
+
+    /// The unit normal of the plane.
+    normal: Vec3,
+
+    /// `n / (n · n)` for the (non-unit) normal `n = u × v`, which maps a
+    /// point on the plane to its coordinates along the edges.
+    w: Vec3,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Material) -> Self {
+        let n = u.cross(&v);
+
+        Self {
+            q,
+            u,
+            v,
+            material,
+            normal: n.unit(),
+            w: n / n.length_squared(),
+        }
+    }
+}
+
+impl Object for Quad {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // parallel to the plane
+        let denom = self.normal.dot(&ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.q - ray.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        // the coordinates along the edges must be both in [0, 1]
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let is_front = denom < 0.0;
+        let normal = if is_front { self.normal } else { -self.normal };
+
+        Some(HitRecord {
+            t,
+            p,
+            normal,
+            is_front,
+            u: alpha,
+            v: beta,
+            dpdu: self.u,
+            dpdv: self.v,
+            material: &self.material,
+            vertex_color: None,
+            object: 0,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.q, self.q + self.u + self.v)
+            .union(&Aabb::new(self.q + self.u, self.q + self.v))
+    }
+}
+
+/// An axis-aligned box, made of 6 quads facing outwards.
+#[derive(Debug, Clone)]
+pub struct Cuboid {
+    sides: [Quad; 6],
+}
+
+impl Cuboid {
+    /// The box with two opposite corners.
+    pub fn new(a: Point3, b: Point3, material: Material) -> Self {
+        let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+        let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+
+        let side = |q: Point3, u: Vec3, v: Vec3| Quad::new(q, u, v, material.clone());
+
+        Self {
+            sides: [
+                side(Point3::new(min.x(), min.y(), max.z()), dx, dy), // front
+                side(Point3::new(max.x(), min.y(), max.z()), -dz, dy), // right
+                side(Point3::new(max.x(), min.y(), min.z()), -dx, dy), // back
+                side(Point3::new(min.x(), min.y(), min.z()), dz, dy), // left
+                side(Point3::new(min.x(), max.y(), max.z()), dx, -dz), // top
+                side(Point3::new(min.x(), min.y(), min.z()), dx, dz), // bottom
+            ],
+        }
+    }
+}
+
+impl Object for Cuboid {
+    fn hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.sides
+            .iter()
+            .filter_map(|side| side.hit_by(ray, t_min, t_max))
+            .min_by(|x, y| x.t.total_cmp(&y.t))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sides
+            .iter()
+            .map(|side| side.bounding_box())
+            .reduce(|acc, bbox| acc.union(&bbox))
+            .unwrap()
+    }
+}
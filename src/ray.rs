@@ -3,19 +3,29 @@
 //! To determine the color of each pixel in the output image, the camera sends
 //! out rays from the viewer to points in the viewport, and asks for the color
 //! of a ray, which is in turn determined by its interaction with the world.
+//!
+//! In spectral mode, a ray additionally carries a single wavelength, and its
+//! "color" is the radiance at that wavelength, stored in all three channels.
 
 use crate::object::Object;
-use crate::{Color, Point3, RngExt, Vec3, World, INF};
+use crate::{spectrum, Color, Point3, Sampler, Vec3, World, INF};
 
 /// A ray with an origin and a direction.
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+
+    /// The wavelength in nanometers, only present in spectral mode.
+    wavelength: Option<f64>,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            wavelength: None,
+        }
     }
 
     pub fn origin(&self) -> Point3 {
@@ -26,6 +36,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
     /// The point where the ray travels at time `t`.
     pub fn at(&self, t: f64) -> Point3 {
         self.origin + t * self.direction
@@ -33,7 +47,7 @@ impl Ray {
 
     /// The color of the ray, given an instance of `World`
     /// and a maximum recursion depth.
-    pub fn color(&self, world: &World, depth: u32, rng: &mut impl RngExt) -> Color {
+    pub fn color(&self, world: &World, depth: u32, sampler: &mut impl Sampler) -> Color {
         // at max depth, return black
         if depth == 0 {
             return Color::default();
@@ -43,26 +57,51 @@ impl Ray {
         // here t_min is set to 0.001 to prevent shadow acne
         // (i.e. the ray hitting its origin on the surface at t=0)
         if let Some(rec) = world.hit_by(self, 0.001, INF) {
-            if let Some(scattered) = rec.material.scatter(self, &rec, rng) {
+            // the light emitted by the surface itself, if any
+            let emitted = self.project(rec.material.emitted(self, &rec));
+
+            if let Some(mut scattered) = rec.material.scatter(self, &rec, sampler) {
+                // the child ray carries on the same wavelength
+                scattered.ray.wavelength = self.wavelength;
+
                 // if the ray scatters into a child ray,
-                // returns the attenuated color of the child ray
-                return scattered.attenuation * scattered.ray.color(world, depth - 1, rng);
+                // adds the attenuated color of the child ray
+                let attenuation = self.project(scattered.attenuation);
+                return emitted + attenuation * scattered.ray.color(world, depth - 1, sampler);
             } else {
-                // otherwise the ray is absorbed, return black
-                return Color::default();
+                // otherwise the ray is absorbed
+                return emitted;
             };
         }
 
-        // if the ray hit nothing, lerp between whith and blue
-        // according to its y component, i.e. how high it aims
-        //
-        // TODO: can we make them static?
-        let white = Color::new(1.0, 1.0, 1.0);
-        let blue = Color::new(0.5, 0.7, 1.0);
+        // if the ray hit nothing, it sees the background
+        self.project(world.background().color(self.direction))
+    }
+
+    /// The color of the ray in spectral mode, with the same arguments
+    /// as `color`. A single wavelength is sampled for the whole path.
+    pub fn spectral_color(&self, world: &World, depth: u32, sampler: &mut impl Sampler) -> Color {
+        let lambda = spectrum::sample_wavelength(sampler.next_1d());
+        let ray = Self {
+            wavelength: Some(lambda),
+            ..*self
+        };
 
-        let u = self.direction.unit();
-        let t = 0.5 * (u.y() + 1.0);
+        // all channels hold the same radiance
+        let radiance = ray.color(world, depth, sampler).r();
 
-        (1.0 - t) * white + t * blue
+        spectrum::to_color(lambda, radiance)
+    }
+
+    /// Projects a RGB color to the wavelength of the ray in spectral mode,
+    /// or leaves it as is otherwise.
+    fn project(&self, color: Color) -> Color {
+        match self.wavelength {
+            Some(lambda) => {
+                let x = spectrum::uplift(color, lambda);
+                Color::new(x, x, x)
+            }
+            None => color,
+        }
     }
 }
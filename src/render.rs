@@ -0,0 +1,657 @@
+//! Rendering a world into framebuffers.
+//!
+//! The image is rendered in parallel by square tiles. Samples only depend on
+//! the seed and their pixel, and the tiles are merged in order, so the result
+//! is deterministic regardless of the scheduling.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::object::{HitRecord, Object};
+use crate::sampler::{hash, HaltonSampler, IndependentSampler, SobolSampler, StratifiedSampler};
+use crate::{
+    Camera, Color, Filter, Framebuffer, Point3, Ray, Sampler, SamplerKind, Vec3, World, INF,
+};
+
+/// Renders images of a world, with the given settings.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    max_depth: u32,
+    seed: u64,
+
+    /// Whether to trace paths by wavelengths, see `Ray::spectral_color`.
+    spectral: bool,
+
+    /// The pixel reconstruction filter.
+    filter: Filter,
+
+    /// The sample generator.
+    sampler: SamplerKind,
+
+    /// Whether to produce the arbitrary output values.
+    aovs: bool,
+
+    /// The width and height of the tiles rendered by each parallel task.
+    tile_size: u32,
+
+    /// If set, pixels stop sampling once they are converged,
+    /// with the samples per pixel being the maximum.
+    adaptive: Option<Adaptive>,
+}
+
+/// A finished tile of the image.
+#[derive(Debug, Clone)]
+pub struct Tile {
+    /// The position of the top left corner in the image.
+    pub x: u32,
+    pub y: u32,
+
+    pub width: u32,
+    pub height: u32,
+
+    /// The colors, row by row from the top. They only include the samples
+    /// taken within the tile, so with a filter wider than a pixel they may
+    /// differ slightly from the final image near the edges of the tile.
+    pub pixels: Vec<Color>,
+}
+
+/// A token to cancel a render from another thread. Clones share the token.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the renders using this token to stop. Tiles in progress
+    /// are finished, but no more are started.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The arbitrary output values (AOVs), i.e. auxiliary images for compositing
+/// and debugging, collected from the primary rays at their first hit.
+///
+/// Pixels where nothing is hit are black, i.e. zero in all channels.
+pub struct Aovs {
+    /// The surface normal, facing against the ray.
+    pub normal: Framebuffer,
+
+    /// The distance from the camera, in all channels.
+    pub depth: Framebuffer,
+
+    /// The surface color, see `Material::albedo`.
+    pub albedo: Framebuffer,
+
+    /// The id of the material starting from 1, in all channels.
+    ///
+    /// Ids are numbered by the order of appearance in the tiles,
+    /// and are taken from the first sample of each pixel.
+    pub material_id: Framebuffer,
+
+    /// The id of the object starting from 1, in all channels.
+    /// That is, the index of the object in the world plus one.
+    ///
+    /// Ids are taken from the first sample of each pixel.
+    pub object_id: Framebuffer,
+
+    /// The position of the hit point in world space.
+    pub position: Framebuffer,
+}
+
+impl Aovs {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            normal: Framebuffer::new(width, height),
+            depth: Framebuffer::new(width, height),
+            albedo: Framebuffer::new(width, height),
+            material_id: Framebuffer::new(width, height),
+            object_id: Framebuffer::new(width, height),
+            position: Framebuffer::new(width, height),
+        }
+    }
+
+    /// All buffers along with their names, e.g. for writing them to files.
+    pub fn buffers(&self) -> [(&'static str, &Framebuffer); 6] {
+        [
+            ("normal", &self.normal),
+            ("depth", &self.depth),
+            ("albedo", &self.albedo),
+            ("material_id", &self.material_id),
+            ("object_id", &self.object_id),
+            ("position", &self.position),
+        ]
+    }
+}
+
+/// The result of a render.
+pub struct Frame {
+    /// The color image, in linear radiance.
+    pub color: Framebuffer,
+
+    /// The AOVs, if requested.
+    pub aovs: Option<Aovs>,
+
+    /// The number of samples spent on each pixel relative to the maximum,
+    /// in all channels, if sampling is adaptive. It shows as a heatmap
+    /// where brighter pixels are harder to converge.
+    pub samples: Option<Framebuffer>,
+}
+
+/// The settings of adaptive sampling.
+#[derive(Debug, Clone, Copy)]
+struct Adaptive {
+    /// The relative error of a pixel at which it stops sampling.
+    threshold: f64,
+
+    /// The number of samples before checking the error.
+    min_samples: u32,
+}
+
+/// The running mean and variance of the luminance of samples,
+/// with Welford's algorithm. `n` is also the number of samples taken.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Stats {
+    pub(crate) n: u32,
+    pub(crate) mean: f64,
+    pub(crate) m2: f64,
+}
+
+impl Stats {
+    fn add(&mut self, x: f64) {
+        self.n += 1;
+        let d = x - self.mean;
+        self.mean += d / self.n as f64;
+        self.m2 += d * (x - self.mean);
+    }
+
+    /// Whether the 95% confidence interval of the mean is within
+    /// `threshold` relative to the mean.
+    ///
+    /// Very dark pixels are compared against a small floor instead,
+    /// so that they can stop despite their relative error.
+    fn converged(&self, threshold: f64) -> bool {
+        if self.n < 2 {
+            return false;
+        }
+
+        let variance = self.m2 / (self.n - 1) as f64;
+        let half_width = 1.96 * (variance / self.n as f64).sqrt();
+
+        half_width <= threshold * self.mean.max(0.01)
+    }
+}
+
+/// The AOVs of a pixel, accumulated over its samples.
+#[derive(Default)]
+struct AovSamples {
+    /// The number of samples, including the ones hitting nothing.
+    count: u32,
+
+    normal: Color,
+    depth: f64,
+    albedo: Color,
+    position: Color,
+
+    /// The address of the material hit by the first sample,
+    /// to be numbered after all pixels are done.
+    material: Option<usize>,
+
+    /// The object hit by the first sample.
+    object: Option<usize>,
+}
+
+impl AovSamples {
+    fn add(&mut self, ray: &Ray, rec: &HitRecord, first: bool) {
+        let to_color = |v: Vec3| Color::new(v.x(), v.y(), v.z());
+
+        self.normal += to_color(rec.normal);
+        self.depth += rec.t * ray.direction().length();
+        self.albedo += rec.material.albedo(rec);
+        self.position += to_color(rec.p - Point3::default());
+
+        if first {
+            self.material = Some(rec.material as *const _ as usize);
+            self.object = Some(rec.object);
+        }
+    }
+}
+
+impl Renderer {
+    /// Creates a renderer of the given image size, with 100 samples per
+    /// pixel, a maximum depth of 50, and seed 0.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            seed: 0,
+            spectral: false,
+            filter: Filter::default(),
+            sampler: SamplerKind::default(),
+            aovs: false,
+            tile_size: 16,
+            adaptive: None,
+        }
+    }
+
+    /// Changes the image size, keeping the other settings.
+    pub fn resolution(self, width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    /// The number of samples per pixel, i.e. the maximum if adaptive.
+    ///
+    /// Panics if it is 0.
+    pub fn samples_per_pixel(self, samples_per_pixel: u32) -> Self {
+        assert!(samples_per_pixel > 0, "samples per pixel must be positive");
+
+        Self {
+            samples_per_pixel,
+            ..self
+        }
+    }
+
+    pub fn max_depth(self, max_depth: u32) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// The renderer of a frame of an animation, whose seed is hashed from
+    /// this seed and the frame number. So the noise changes between frames,
+    /// and a frame renders the same alone or in a sequence.
+    pub fn for_frame(&self, frame: u32) -> Self {
+        Self {
+            seed: hash(&[self.seed, frame as u64]),
+            ..self.clone()
+        }
+    }
+
+    /// Traces paths by wavelengths instead of RGB, for dispersion.
+    pub fn spectral(self, spectral: bool) -> Self {
+        Self { spectral, ..self }
+    }
+
+    /// The pixel reconstruction filter, a box of radius 0.5 by default.
+    ///
+    /// Panics if its radius is not positive and finite.
+    pub fn filter(self, filter: Filter) -> Self {
+        assert!(
+            filter.is_valid(),
+            "filter radius {} is not positive",
+            filter.radius()
+        );
+
+        Self { filter, ..self }
+    }
+
+    /// The sample generator, independent random samples by default.
+    pub fn sampler(self, sampler: SamplerKind) -> Self {
+        Self { sampler, ..self }
+    }
+
+    /// Also produces the AOVs along with the color image.
+    pub fn aovs(self, aovs: bool) -> Self {
+        Self { aovs, ..self }
+    }
+
+    /// The size of the square tiles, 16 pixels by default.
+    pub fn tile_size(self, tile_size: u32) -> Self {
+        assert!(tile_size > 0, "tile size must be positive");
+
+        Self { tile_size, ..self }
+    }
+
+    /// Samples each pixel adaptively: after `min_samples`, a pixel stops
+    /// once the 95% confidence interval of its luminance is within
+    /// `threshold` relative to the mean (e.g. 0.01 for 1%), or when the
+    /// samples per pixel are used up.
+    pub fn adaptive(self, threshold: f64, min_samples: u32) -> Self {
+        Self {
+            adaptive: Some(Adaptive {
+                threshold,
+                min_samples,
+            }),
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of samples per pixel, i.e. the maximum if adaptive.
+    pub(crate) fn max_samples(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    /// The settings which change the image, i.e. all but the tile size and
+    /// the AOVs, in little-endian bytes for the checkpoints to be matched.
+    pub(crate) fn image_settings(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(self.width.to_le_bytes());
+        bytes.extend(self.height.to_le_bytes());
+        bytes.extend(self.samples_per_pixel.to_le_bytes());
+        bytes.extend(self.max_depth.to_le_bytes());
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.push(self.spectral as u8);
+
+        let (kind, params) = match self.filter {
+            Filter::Box { radius } => (0, [radius, 0.0, 0.0]),
+            Filter::Tent { radius } => (1, [radius, 0.0, 0.0]),
+            Filter::Gaussian { radius, alpha } => (2, [radius, alpha, 0.0]),
+            Filter::Mitchell { radius, b, c } => (3, [radius, b, c]),
+        };
+        bytes.push(kind);
+        for param in params {
+            bytes.extend(param.to_le_bytes());
+        }
+
+        bytes.push(match self.sampler {
+            SamplerKind::Independent => 0,
+            SamplerKind::Stratified => 1,
+            SamplerKind::Halton => 2,
+            SamplerKind::Sobol => 3,
+        });
+
+        match self.adaptive {
+            Some(Adaptive {
+                threshold,
+                min_samples,
+            }) => {
+                bytes.push(1);
+                bytes.extend(threshold.to_le_bytes());
+                bytes.extend(min_samples.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+
+        bytes
+    }
+
+    /// Renders the world as seen by the camera.
+    pub fn render(&self, world: &World, camera: &dyn Camera) -> Frame {
+        self.render_with_progress(world, camera, |_| ())
+    }
+
+    /// Renders the world as seen by the camera, calling `progress`
+    /// with the number of pixels finished every time a tile is done.
+    pub fn render_with_progress(
+        &self,
+        world: &World,
+        camera: &dyn Camera,
+        progress: impl Fn(u64) + Sync,
+    ) -> Frame {
+        let on_tile = |tile: &Tile| progress((tile.width * tile.height) as u64);
+        self.render_tiles(world, camera, on_tile, &Cancel::new())
+            .expect("render without cancellation")
+    }
+
+    /// Renders the world as seen by the camera, calling `on_tile` from the
+    /// worker threads as each tile is finished, e.g. to show it in a GUI or
+    /// to send it through a channel. Tiles are started row by row from the
+    /// top, though they may finish in any order.
+    ///
+    /// Returns `None` if cancelled before all tiles are done.
+    pub fn render_tiles(
+        &self,
+        world: &World,
+        camera: &dyn Camera,
+        on_tile: impl Fn(&Tile) + Sync,
+        cancel: &Cancel,
+    ) -> Option<Frame> {
+        let mut stats = vec![Stats::default(); (self.width * self.height) as usize];
+        let end = self.samples_per_pixel;
+        let (color, aovs) = self.render_samples(world, camera, &mut stats, end, on_tile, cancel)?;
+
+        Some(Frame {
+            color,
+            aovs,
+            samples: self.sample_counts(&stats),
+        })
+    }
+
+    /// The heatmap of the samples taken by each pixel, if sampling is adaptive.
+    pub(crate) fn sample_counts(&self, stats: &[Stats]) -> Option<Framebuffer> {
+        self.adaptive?;
+
+        let mut samples = Framebuffer::new(self.width, self.height);
+        for (i, stats) in stats.iter().enumerate() {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            let v = stats.n as f64 / self.samples_per_pixel as f64;
+            samples.add_sample(x, y, Color::new(v, v, v));
+        }
+
+        Some(samples)
+    }
+
+    /// Continues sampling each pixel from where its `stats` left off,
+    /// until it has `end` samples (or converges), and returns the new
+    /// samples only, along with their AOVs if requested.
+    ///
+    /// If cancelled, returns `None` and leaves `stats` untouched.
+    pub(crate) fn render_samples(
+        &self,
+        world: &World,
+        camera: &dyn Camera,
+        stats: &mut [Stats],
+        end: u32,
+        on_tile: impl Fn(&Tile) + Sync,
+        cancel: &Cancel,
+    ) -> Option<(Framebuffer, Option<Aovs>)> {
+        let seed = self.seed;
+
+        match self.sampler {
+            SamplerKind::Independent => {
+                let sampler = IndependentSampler::new(seed);
+                self.render_by(sampler, world, camera, stats, end, &on_tile, cancel)
+            }
+            SamplerKind::Stratified => {
+                let sampler = StratifiedSampler::new(seed, self.samples_per_pixel);
+                self.render_by(sampler, world, camera, stats, end, &on_tile, cancel)
+            }
+            SamplerKind::Halton => {
+                let sampler = HaltonSampler::new(seed);
+                self.render_by(sampler, world, camera, stats, end, &on_tile, cancel)
+            }
+            SamplerKind::Sobol => {
+                let sampler = SobolSampler::new(seed);
+                self.render_by(sampler, world, camera, stats, end, &on_tile, cancel)
+            }
+        }
+    }
+
+    /// Renders with the given sampler, cloned for each parallel task.
+    #[allow(clippy::too_many_arguments)]
+    fn render_by<S: Sampler + Clone + Sync>(
+        &self,
+        sampler: S,
+        world: &World,
+        camera: &dyn Camera,
+        stats: &mut [Stats],
+        end: u32,
+        on_tile: &(impl Fn(&Tile) + Sync),
+        cancel: &Cancel,
+    ) -> Option<(Framebuffer, Option<Aovs>)> {
+        let (width, height) = (self.width, self.height);
+        let size = self.tile_size;
+
+        // splats may reach this many pixels beyond a tile
+        let margin = self.filter.radius().ceil() as u32;
+
+        let corners: Vec<_> = (0..height)
+            .step_by(size as usize)
+            .flat_map(|y| (0..width).step_by(size as usize).map(move |x| (x, y)))
+            .collect();
+
+        let tiles: Vec<_> = corners
+            .into_par_iter()
+            .map(|(x_start, y_start)| {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+
+                let x_end = (x_start + size).min(width);
+                let y_end = (y_start + size).min(height);
+
+                // the framebuffer of the tile, including the margins
+                let x0 = x_start.saturating_sub(margin);
+                let y0 = y_start.saturating_sub(margin);
+                let x1 = (x_end + margin).min(width);
+                let y1 = (y_end + margin).min(height);
+                let mut fb = Framebuffer::new(x1 - x0, y1 - y0);
+
+                let mut sampler = sampler.clone();
+                let mut pixels = vec![];
+                for y in y_start..y_end {
+                    for x in x_start..x_end {
+                        let mut stats = stats[(y * width + x) as usize];
+                        let splat =
+                            |x, y, c| fb.splat(x - x0 as f64, y - y0 as f64, c, &self.filter);
+                        let aov = self.render_pixel(
+                            world,
+                            camera,
+                            x,
+                            y,
+                            &mut sampler,
+                            &mut stats,
+                            end,
+                            splat,
+                        );
+                        pixels.push((x, y, stats, aov));
+                    }
+                }
+
+                on_tile(&Tile {
+                    x: x_start,
+                    y: y_start,
+                    width: x_end - x_start,
+                    height: y_end - y_start,
+                    pixels: pixels
+                        .iter()
+                        .map(|&(x, y, ..)| fb.get(x - x0, y - y0))
+                        .collect(),
+                });
+
+                Some((x0, y0, fb, pixels))
+            })
+            .collect::<Option<_>>()?;
+
+        let mut color = Framebuffer::new(width, height);
+        let mut aovs = self.aovs.then(|| Aovs::new(width, height));
+        let mut material_ids = HashMap::new();
+
+        // merge the tiles in order, so the result is deterministic
+        for (x0, y0, fb, pixels) in tiles {
+            color.merge(&fb, x0, y0);
+
+            for (x, y, pixel_stats, aov) in pixels {
+                stats[(y * width + x) as usize] = pixel_stats;
+
+                if let (Some(aovs), Some(aov)) = (aovs.as_mut(), aov) {
+                    // a pixel without samples keeps its AOVs at 0
+                    let spp = aov.count.max(1) as f64;
+                    aovs.normal.add_sample(x, y, (1.0 / spp) * aov.normal);
+                    aovs.albedo.add_sample(x, y, (1.0 / spp) * aov.albedo);
+                    aovs.position.add_sample(x, y, (1.0 / spp) * aov.position);
+                    let depth = aov.depth / spp;
+                    aovs.depth.add_sample(x, y, Color::new(depth, depth, depth));
+
+                    // number the materials in the order of appearance
+                    let material = aov.material.map_or(0, |address| {
+                        let next = material_ids.len() + 1;
+                        *material_ids.entry(address).or_insert(next)
+                    }) as f64;
+                    let object = aov.object.map_or(0, |i| i + 1) as f64;
+
+                    let gray = |v| Color::new(v, v, v);
+                    aovs.material_id.add_sample(x, y, gray(material));
+                    aovs.object_id.add_sample(x, y, gray(object));
+                }
+            }
+        }
+
+        Some((color, aovs))
+    }
+
+    /// Renders a pixel from sample `stats.n` up to `end`, passing each
+    /// sample to `splat` at its position in the image, and returns the
+    /// AOVs of these samples if requested.
+    #[allow(clippy::too_many_arguments)]
+    fn render_pixel(
+        &self,
+        world: &World,
+        camera: &dyn Camera,
+        x: u32,
+        y: u32,
+        sampler: &mut impl Sampler,
+        stats: &mut Stats,
+        end: u32,
+        mut splat: impl FnMut(f64, f64, Color),
+    ) -> Option<AovSamples> {
+        let mut aov = self.aovs.then(AovSamples::default);
+
+        for i in stats.n..end {
+            if let Some(adaptive) = self.adaptive {
+                if i >= adaptive.min_samples && stats.converged(adaptive.threshold) {
+                    break;
+                }
+            }
+
+            sampler.start_sample(x, y, i);
+            let (dx, dy) = sampler.next_2d();
+
+            // the camera has its origin at the lower left corner,
+            // so the rows are counted from the bottom
+            let row = self.height - 1 - y;
+            let u = (x as f64 + dx) / self.width as f64;
+            let v = (row as f64 + dy) / self.height as f64;
+            let ray = camera.get_ray(u, v, sampler.next_2d());
+
+            if let Some(aov) = aov.as_mut() {
+                aov.count += 1;
+                if let Some(ray) = &ray {
+                    if let Some(rec) = world.hit_by(ray, 0.001, INF) {
+                        aov.add(ray, &rec, i == 0);
+                    }
+                }
+            }
+
+            let color = match &ray {
+                Some(ray) if self.spectral => ray.spectral_color(world, self.max_depth, sampler),
+                Some(ray) => ray.color(world, self.max_depth, sampler),
+                // the projection doesn't cover this point
+                None => Color::default(),
+            };
+
+            stats.add(color.luminance());
+            splat(x as f64 + dx, y as f64 + (1.0 - dy), color);
+        }
+
+        aov
+    }
+}
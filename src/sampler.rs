@@ -0,0 +1,611 @@
+//! Sample generators.
+//!
+//! A path needs a sequence of random numbers: two for the position in the
+//! pixel, two for the lens, then a few for each bounce. Each of them is a
+//! "dimension" of the sample. Independent random numbers converge slowly,
+//! so a sampler may instead spread the samples of a pixel evenly in each
+//! dimension (or pair of dimensions), which reduces the noise.
+//!
+//! All samplers here are stateless apart from the current dimension: a value
+//! only depends on the seed, the pixel, the sample index and the dimension,
+//! so samples can be drawn in any order and rendering is reproducible.
+
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Vec3, PI};
+
+/// A generator of sample values in [0, 1).
+pub trait Sampler {
+    /// Starts the `index`-th sample of pixel `(x, y)`, from dimension 0.
+    fn start_sample(&mut self, x: u32, y: u32, index: u32);
+
+    /// The value of the next dimension.
+    fn next_1d(&mut self) -> f64;
+
+    /// The values of the next two dimensions, which the sampler may
+    /// stratify as a 2D point rather than separately.
+    fn next_2d(&mut self) -> (f64, f64) {
+        (self.next_1d(), self.next_1d())
+    }
+
+    /// Returns a random point in the unit disk, as `(x, y)`.
+    fn in_unit_disk(&mut self) -> (f64, f64) {
+        concentric_disk(self.next_2d())
+    }
+
+    /// Returns a random `Vec3` with unit length.
+    fn on_unit_sphere(&mut self) -> Vec3 {
+        let (u, v) = self.next_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Returns a random `Vec3` in the unit ball, i.e. with length < 1.
+    fn in_unit_ball(&mut self) -> Vec3 {
+        let direction = self.on_unit_sphere();
+        self.next_1d().cbrt() * direction
+    }
+}
+
+/// Maps a point in [0, 1)² to the unit disk by the concentric mapping,
+/// which keeps the stratification.
+pub(crate) fn concentric_disk((u, v): (f64, f64)) -> (f64, f64) {
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Any random number generator is an (independent) sampler,
+/// which simply ignores the pixel and sample index.
+impl<R: rand::RngCore> Sampler for R {
+    fn start_sample(&mut self, _x: u32, _y: u32, _index: u32) {}
+
+    fn next_1d(&mut self) -> f64 {
+        rand::Rng::gen(self)
+    }
+}
+
+/// A 64-bit integer hash (the finalizer of SplitMix64).
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes a sequence of integers.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x9e3779b97f4a7c15, |h, &v| mix(h ^ mix(v.wrapping_add(h))))
+}
+
+/// Maps random bits to a float in [0, 1).
+fn to_float(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// The sample being generated, common to all samplers.
+#[derive(Debug, Clone, Default)]
+struct Cursor {
+    /// The seed of the current pixel.
+    pixel: u64,
+    index: u32,
+    dim: u32,
+}
+
+impl Cursor {
+    fn start(&mut self, seed: u64, x: u32, y: u32, index: u32) {
+        self.pixel = hash(&[seed, x as u64, y as u64]);
+        self.index = index;
+        self.dim = 0;
+    }
+
+    /// Returns the current dimension and moves on by `n`.
+    fn advance(&mut self, n: u32) -> u32 {
+        let dim = self.dim;
+        self.dim += n;
+        dim
+    }
+
+    /// A random value unique to the current sample and a dimension.
+    fn random(&self, dim: u32) -> f64 {
+        to_float(hash(&[self.pixel, self.index as u64, dim as u64]))
+    }
+}
+
+/// Independent uniform random samples, i.e. white noise.
+#[derive(Debug, Clone)]
+pub struct IndependentSampler {
+    seed: u64,
+    cursor: Cursor,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            cursor: Cursor::default(),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.cursor.start(self.seed, x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dim = self.cursor.advance(1);
+        self.cursor.random(dim)
+    }
+}
+
+/// Returns the `i`-th element of a random permutation of `0..l`,
+/// chosen by `p`, as proposed by Kensler (2013).
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    // cycle walking: retry until the result falls within the range
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < l {
+            break;
+        }
+    }
+
+    (i.wrapping_add(p)) % l
+}
+
+/// Jittered stratified samples: with `n` samples per pixel, each dimension
+/// is split into `n` strata, and each stratum gets one sample jittered
+/// inside it. Pairs of dimensions are stratified on a grid when `n` is a
+/// perfect square, otherwise as a Latin hypercube.
+///
+/// Samples beyond the first `n` start over with new strata permutations.
+#[derive(Debug, Clone)]
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    cursor: Cursor,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            samples_per_pixel: samples_per_pixel.max(1),
+            cursor: Cursor::default(),
+        }
+    }
+
+    /// The stratum of the current sample in a dimension with `n` strata.
+    fn stratum(&self, dim: u32, n: u32) -> u32 {
+        let index = self.cursor.index % n;
+        let round = (self.cursor.index / n) as u64;
+        let p = hash(&[self.cursor.pixel, dim as u64, round]) as u32;
+
+        permutation_element(index, n, p)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.cursor.start(self.seed, x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let n = self.samples_per_pixel;
+        let dim = self.cursor.advance(1);
+
+        (self.stratum(dim, n) as f64 + self.cursor.random(dim)) / n as f64
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let n = self.samples_per_pixel;
+        let k = (n as f64).sqrt().round() as u32;
+        if k * k != n {
+            return (self.next_1d(), self.next_1d());
+        }
+
+        let dim = self.cursor.advance(2);
+        let s = self.stratum(dim, n);
+        let u = ((s % k) as f64 + self.cursor.random(dim)) / k as f64;
+        let v = ((s / k) as f64 + self.cursor.random(dim + 1)) / k as f64;
+
+        (u, v)
+    }
+}
+
+/// The number of dimensions of the Halton sequence, beyond which
+/// independent random values are used.
+const HALTON_DIMS: usize = 64;
+
+/// The first primes, as the bases of the Halton sequence.
+static PRIMES: Lazy<Vec<u32>> = Lazy::new(|| {
+    (2..)
+        .filter(|&n: &u32| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+        .take(HALTON_DIMS)
+        .collect()
+});
+
+/// Mirrors the digits of `i` in `base` around the decimal point.
+fn radical_inverse(mut i: u32, base: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_bi = 1.0;
+    let mut reversed = 0.0;
+
+    while i > 0 {
+        inv_bi *= inv_base;
+        reversed += (i % base) as f64 * inv_bi;
+        i /= base;
+    }
+
+    reversed
+}
+
+/// The Halton low-discrepancy sequence, with the i-th prime as the base
+/// of the i-th dimension. Each pixel uses the sequence from the start,
+/// decorrelated by a random shift (Cranley-Patterson rotation).
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    seed: u64,
+    cursor: Cursor,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            cursor: Cursor::default(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.cursor.start(self.seed, x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dim = self.cursor.advance(1);
+
+        match PRIMES.get(dim as usize) {
+            Some(&base) => {
+                let shift = to_float(hash(&[self.cursor.pixel, dim as u64]));
+                let v = radical_inverse(self.cursor.index, base) + shift;
+                v - v.floor()
+            }
+            None => self.cursor.random(dim),
+        }
+    }
+}
+
+/// The direction numbers of the first 4 dimensions of the Sobol sequence,
+/// from the primitive polynomials and initial numbers of Joe and Kuo.
+static SOBOL_DIRECTIONS: Lazy<[[u32; 32]; 4]> = Lazy::new(|| {
+    let mut v = [[0; 32]; 4];
+
+    // the first dimension is the van der Corput sequence
+    for (k, v) in v[0].iter_mut().enumerate() {
+        *v = 1 << (31 - k);
+    }
+
+    // (degree, coefficients, initial numbers) of the other dimensions
+    let params: [(usize, u32, &[u32]); 3] = [(1, 0, &[1]), (2, 1, &[1, 3]), (3, 1, &[1, 3, 1])];
+
+    for (d, (s, a, m)) in params.into_iter().enumerate() {
+        let v = &mut v[d + 1];
+        for k in 0..s {
+            v[k] = m[k] << (31 - k);
+        }
+        for k in s..32 {
+            v[k] = v[k - s] ^ (v[k - s] >> s);
+            for l in 1..s {
+                if (a >> (s - 1 - l)) & 1 == 1 {
+                    v[k] ^= v[k - l];
+                }
+            }
+        }
+    }
+
+    v
+});
+
+/// The `index`-th point of the Sobol sequence in one of the first 4
+/// dimensions, as 32 fractional bits.
+fn sobol(mut index: u32, dim: usize) -> u32 {
+    let mut x = 0;
+    let mut k = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            x ^= SOBOL_DIRECTIONS[dim][k];
+        }
+        index >>= 1;
+        k += 1;
+    }
+
+    x
+}
+
+/// A hash-based Owen scrambling of the bits of `x`,
+/// from the most significant one (Laine and Karras, 2011).
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+
+    x.reverse_bits()
+}
+
+/// The Sobol sequence with Owen scrambling, following Burley (2020).
+///
+/// Dimensions are taken in groups of 4 from the Sobol sequence, each group
+/// scrambled and shuffled independently, so there is no limit in dimensions.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    seed: u64,
+    cursor: Cursor,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            cursor: Cursor::default(),
+        }
+    }
+
+    fn sample(&self, dim: u32) -> f64 {
+        let group_seed = hash(&[self.cursor.pixel, (dim / 4) as u64]);
+        let index = owen_scramble(self.cursor.index, group_seed as u32);
+
+        let d = (dim % 4) as usize;
+        let bits = owen_scramble(sobol(index, d), hash(&[group_seed, d as u64]) as u32);
+
+        bits as f64 / (1u64 << 32) as f64
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.cursor.start(self.seed, x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dim = self.cursor.advance(1);
+        self.sample(dim)
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        // keep pairs within a group, so they are stratified together
+        if self.cursor.dim % 2 == 1 {
+            self.cursor.advance(1);
+        }
+
+        let dim = self.cursor.advance(2);
+        (self.sample(dim), self.sample(dim + 1))
+    }
+}
+
+/// The kinds of samplers a renderer can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SamplerKind {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The samplers of all kinds, with 16 samples per pixel if stratified.
+    fn samplers(seed: u64) -> Vec<Box<dyn Sampler>> {
+        vec![
+            Box::new(IndependentSampler::new(seed)),
+            Box::new(StratifiedSampler::new(seed, 16)),
+            Box::new(HaltonSampler::new(seed)),
+            Box::new(SobolSampler::new(seed)),
+        ]
+    }
+
+    /// The first `n` samples of a pixel, each with `dims` values
+    /// taken in pairs.
+    fn samples_2d(sampler: &mut dyn Sampler, n: u32, dims: u32) -> Vec<Vec<f64>> {
+        (0..n)
+            .map(|index| {
+                sampler.start_sample(3, 5, index);
+                (0..dims / 2)
+                    .flat_map(|_| {
+                        let (u, v) = sampler.next_2d();
+                        [u, v]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Whether each of the `n` intervals of [0, 1) has one of `n` values.
+    fn one_per_interval(values: impl Iterator<Item = f64>, n: usize) -> bool {
+        let mut counts = vec![0; n];
+        for v in values {
+            counts[(v * n as f64) as usize] += 1;
+        }
+        counts.iter().all(|&count| count == 1)
+    }
+
+    #[test]
+    fn samples_are_in_unit_interval() {
+        for mut sampler in samplers(7) {
+            for index in 0..64 {
+                sampler.start_sample(index % 5, index / 5, index);
+                for _ in 0..80 {
+                    let v = sampler.next_1d();
+                    assert!((0.0..1.0).contains(&v), "{}", v);
+                }
+                let (u, v) = sampler.next_2d();
+                assert!((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v));
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_seed_repeats() {
+        let (first, again, other) = (samplers(7), samplers(7), samplers(8));
+        for ((mut first, mut again), mut other) in first.into_iter().zip(again).zip(other) {
+            let first = samples_2d(first.as_mut(), 8, 10);
+            assert_eq!(first, samples_2d(again.as_mut(), 8, 10));
+            assert_ne!(first, samples_2d(other.as_mut(), 8, 10));
+        }
+    }
+
+    #[test]
+    fn sobol_is_stratified() {
+        for seed in 0..4 {
+            let mut sampler = SobolSampler::new(seed);
+
+            for k in 0..=8 {
+                let n = 1 << k;
+                let samples = samples_2d(&mut sampler, n, 8);
+
+                // each dimension alone
+                for dim in 0..8 {
+                    let values = samples.iter().map(|s| s[dim]);
+                    assert!(one_per_interval(values, n as usize), "k {} dim {}", k, dim);
+                }
+
+                // the first pair in all elementary intervals of 2^-a by 2^-b
+                for a in 0..=k {
+                    let (columns, rows) = (1 << a, 1 << (k - a));
+                    let cells = samples.iter().map(|s| {
+                        let (i, j) = ((s[0] * columns as f64) as u32, (s[1] * rows as f64) as u32);
+                        (i * rows + j) as f64 / n as f64
+                    });
+                    assert!(one_per_interval(cells, n as usize), "k {} a {}", k, a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_is_stratified() {
+        let n = 16;
+        let mut sampler = StratifiedSampler::new(3, n);
+
+        // every round of `n` samples, in 1D and on the grid of pairs
+        for round in 0..2 {
+            let samples: Vec<_> = (round * n..(round + 1) * n)
+                .map(|index| {
+                    sampler.start_sample(1, 2, index);
+                    let (u, v) = sampler.next_2d();
+                    (u, v, sampler.next_1d())
+                })
+                .collect();
+
+            assert!(one_per_interval(samples.iter().map(|s| s.2), n as usize));
+            let cells = samples
+                .iter()
+                .map(|&(u, v, _)| ((u * 4.0).floor() * 4.0 + (v * 4.0).floor()) / 16.0);
+            assert!(one_per_interval(cells, n as usize));
+        }
+    }
+
+    #[test]
+    fn permutation_elements() {
+        for l in [1, 2, 3, 7, 16, 100] {
+            for p in [0, 1, 0xdeadbeef] {
+                let mut elements: Vec<_> = (0..l).map(|i| permutation_element(i, l, p)).collect();
+                elements.sort_unstable();
+                assert_eq!(elements, (0..l).collect::<Vec<_>>(), "l {} p {}", l, p);
+            }
+        }
+    }
+
+    #[test]
+    fn owen_scramble_keeps_intervals() {
+        // the first `k` bits of a value are scrambled by those before,
+        // so values in distinct intervals of 2^-k stay in distinct ones
+        for seed in [0, 1, 0x12345678] {
+            for k in 1..=8 {
+                let mut prefixes: Vec<_> = (0..1u32 << k)
+                    .map(|i| owen_scramble(i << (32 - k), seed) >> (32 - k))
+                    .collect();
+                prefixes.sort_unstable();
+                assert_eq!(prefixes, (0..1 << k).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn sobol_directions() {
+        // the first points of the first two dimensions
+        let points: Vec<_> = (0..4)
+            .map(|i| (sobol(i, 0) >> 30, sobol(i, 1) >> 30))
+            .collect();
+        assert_eq!(points, [(0, 0), (2, 2), (1, 3), (3, 1)]);
+    }
+
+    #[test]
+    fn halton_is_stratified() {
+        assert_eq!(radical_inverse(0, 2), 0.0);
+        assert_eq!(radical_inverse(1, 2), 0.5);
+        assert_eq!(radical_inverse(6, 2), 0.375);
+        assert!((radical_inverse(5, 3) - 7.0 / 9.0).abs() < 1e-15);
+
+        // the first b^k points in each dimension, even when shifted
+        let mut sampler = HaltonSampler::new(5);
+        for (dim, n) in [(0, 32), (1, 27), (2, 25), (3, 49)] {
+            let values = (0..n).map(|index| {
+                sampler.start_sample(0, 0, index);
+                (0..=dim).map(|_| sampler.next_1d()).last().unwrap()
+            });
+            assert!(one_per_interval(values, n as usize), "dim {}", dim);
+        }
+    }
+}
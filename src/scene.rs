@@ -0,0 +1,809 @@
+//! Scene description files.
+//!
+//! A scene is written in TOML, with the render settings, the camera, a table
+//! of named materials, and lists of objects by their kinds, which refer to
+//! the materials by name so that a material can be shared by many objects:
+//!
+//! ```toml
+//! [render]
+//! width = 600
+//! height = 400
+//! samples_per_pixel = 100
+//!
+//! [camera]
+//! look_from = [13, 2, 3]
+//! look_at = [0, 0, 0]
+//! vfov = 20
+//! aperture = 0.1
+//! focus_dist = 10
+//!
+//! [materials.ground]
+//! type = "lambertian"
+//! albedo = [0.5, 0.5, 0.5]
+//!
+//! [materials.glass]
+//! type = "dielectric"
+//! refractive_index = 1.5
+//!
+//! [[spheres]]
+//! center = [0, -1000, 0]
+//! radius = 1000
+//! material = "ground"
+//!
+//! [[spheres]]
+//! center = [0, 1, 0]
+//! radius = 1
+//! material = "glass"
+//! ```
+//!
+//! Colors, points and vectors are arrays of three numbers. In `[render]`,
+//! only the size is required, and `sampler` is one of `"independent"`,
+//! `"stratified"`, `"halton"` or `"sobol"`. A `filter` is a table with
+//! a `type` (`"box"`, `"tent"`, `"gaussian"` or `"mitchell"`) and a `radius`.
+//!
+//! In `[camera]`, `vup` defaults to `[0, 1, 0]`, `vfov` to 90, `aperture` to
+//! 0, `focus_dist` to the distance to `look_at`, and `aspect_ratio` to that
+//! of the image, divided by the `squeeze` so that the image is not squeezed.
+//! A `projection` is a table with a `type` (`"perspective"` by default,
+//! `"orthographic"` with the `height` of the view in the world, `"fisheye"`
+//! with `vfov` up to 360, or `"equirectangular"` for a panorama of 2:1). An
+//! `aperture_shape` is a table with a `type` (`"disk"` by default,
+//! `"polygon"` with the number of `blades` and their `rotation` in degrees,
+//! or `"mask"` with the `path` of a PNG image, relative to the scene file).
+//! `squeeze` is the anamorphic squeeze of the lens, and `tilt` the
+//! angle of the plane of focus in degrees, positive to lay it back.
+//!
+//! An `[animation]` moves the camera through `[[animation.keys]]`, each with
+//! a `time` in seconds and any of `look_from`, `look_at`, `vfov` and
+//! `focus_dist`, which are otherwise those of `[camera]`. It has a frame rate
+//! `fps` (24 by default) and an `interpolation`, `"linear"` (the default) or
+//! `"catmull_rom"`. With `loop = true`, the last key is the same as the
+//! first, and is left out of the frames so that the sequence loops.
+//!
+//! The materials are (with their fields besides `type`):
+//!
+//! - `lambertian`: `albedo`
+//! - `oren_nayar`: `albedo`, `sigma` in degrees
+//! - `sheen`: `albedo`, `tint`, `roughness` in [0, 1]
+//! - `metal`: `albedo`, `fuzz` (0 by default)
+//! - `dielectric`: positive `refractive_index`
+//! - `dispersive`: `dispersion`, either `"bk7"`, `{ cauchy = { a, b } }`
+//!   or `{ sellmeier = { b, c } }` with three coefficients each
+//! - `subsurface`: `albedo`, positive `mean_free_path` and `refractive_index`
+//! - `mix`: `first` and `second` as material names, `weight` of the second
+//!   in [0, 1]
+//!
+//! A sphere has a `center`, a `radius`, negative for a hollow sphere whose
+//! normals point inwards, and a `material`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::{de, Deserialize, Deserializer};
+use toml::Spanned;
+
+use crate::{
+    Aperture, ApertureMask, Camera, CameraBuilder, CameraError, CameraPath, Color, Dispersion,
+    Filter, Interpolation, Material, Point3, Renderer, SamplerKind, Sphere, Vec3, World,
+};
+
+/// A scene loaded from a description file.
+pub struct Scene {
+    pub world: World,
+    pub camera: Box<dyn Camera>,
+
+    /// The renderer with the settings of the scene.
+    pub renderer: Renderer,
+
+    /// The camera being built and its projection, to rebuild it
+    /// for a new size.
+    camera_builder: CameraBuilder,
+    projection: ProjectionDesc,
+
+    /// The camera animation and its frame rate, if any.
+    animation: Option<(CameraPath, f64)>,
+}
+
+/// An error in loading a scene.
+#[derive(Debug)]
+pub enum SceneError {
+    /// The file cannot be read.
+    Io(io::Error),
+
+    /// The description is invalid, at the given line and column (from 1).
+    Invalid {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Invalid {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Scene {
+    /// Loads a scene from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+
+        Self::parse(&std::fs::read_to_string(path)?, base)
+    }
+
+    /// Parses a scene from its description, with the files it refers to
+    /// relative to the current directory.
+    pub fn from_toml(src: &str) -> Result<Self, SceneError> {
+        Self::parse(src, Path::new(""))
+    }
+
+    /// Parses a scene, with the files it refers to relative to `base`.
+    fn parse(src: &str, base: &Path) -> Result<Self, SceneError> {
+        let invalid = |span: Option<Range<usize>>, message: String| {
+            let (line, column) = position(src, span.map_or(0, |span| span.start));
+            SceneError::Invalid {
+                line,
+                column,
+                message,
+            }
+        };
+
+        let desc: SceneDesc =
+            toml::from_str(src).map_err(|err| invalid(err.span(), err.message().to_owned()))?;
+
+        desc.build(base)
+            .map_err(|(span, message)| invalid(Some(span), message))
+    }
+
+    /// Changes the size of the image, and the aspect ratio of the camera
+    /// accordingly, even if the scene sets it.
+    pub fn set_resolution(&mut self, width: u32, height: u32) -> Result<(), CameraError> {
+        let aspect_ratio = self
+            .projection
+            .aspect_ratio(&self.camera_builder, width, height);
+        let builder = self.camera_builder.clone().aspect_ratio(aspect_ratio);
+        self.camera = self.projection.build(builder.clone())?;
+        self.camera_builder = builder;
+        self.renderer = self.renderer.clone().resolution(width, height);
+
+        Ok(())
+    }
+
+    /// The frames of the camera animation, from the first key to the last
+    /// at the frame rate, or `None` if the camera is still. The last key
+    /// of a loop is left out, since it is the same as the first.
+    pub fn frames(&self) -> Option<Range<u32>> {
+        let (path, fps) = self.animation.as_ref()?;
+        let (start, end) = path.time_range();
+        let start = (start * fps).ceil().max(0.0) as u32;
+        let end = if path.is_looped() {
+            (end * fps).ceil()
+        } else {
+            (end * fps).floor() + 1.0
+        };
+        let end = end.max(0.0) as u32;
+
+        Some(start..end.max(start))
+    }
+
+    /// The camera at a frame of the animation, or the camera
+    /// of the scene if it is still.
+    pub fn camera_at(&self, frame: u32) -> Result<Box<dyn Camera>, CameraError> {
+        match &self.animation {
+            Some((path, fps)) => {
+                let aspect_ratio = self.camera_builder.params.aspect_ratio;
+                let builder = path.at(frame as f64 / fps).aspect_ratio(aspect_ratio);
+                self.projection.build(builder)
+            }
+            None => self.projection.build(self.camera_builder.clone()),
+        }
+    }
+}
+
+/// The line and column (from 1) of a byte offset in the source.
+fn position(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+    (line, column)
+}
+
+/// An error found after parsing, at a span of the source.
+type Invalid = (Range<usize>, String);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    render: RenderDesc,
+    camera: Spanned<CameraDesc>,
+
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialEntry>>,
+
+    #[serde(default)]
+    spheres: Vec<SphereDesc>,
+
+    animation: Option<AnimationDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenderDesc {
+    width: Spanned<u32>,
+    height: Spanned<u32>,
+    samples_per_pixel: Option<Spanned<u32>>,
+    max_depth: Option<u32>,
+    seed: Option<u64>,
+    spectral: Option<bool>,
+    sampler: Option<SamplerDesc>,
+    filter: Option<Spanned<FilterDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SamplerDesc {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum FilterDesc {
+    Box { radius: f64 },
+    Tent { radius: f64 },
+    Gaussian { radius: f64 },
+    Mitchell { radius: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    vup: Option<[f64; 3]>,
+    vfov: Option<f64>,
+    aspect_ratio: Option<f64>,
+    aperture: Option<f64>,
+    focus_dist: Option<f64>,
+    aperture_shape: Option<ApertureDesc>,
+    squeeze: Option<f64>,
+    tilt: Option<f64>,
+
+    #[serde(default)]
+    projection: ProjectionDesc,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationDesc {
+    fps: Option<Spanned<f64>>,
+    interpolation: Option<InterpolationDesc>,
+
+    #[serde(default, rename = "loop")]
+    looped: bool,
+
+    keys: Spanned<Vec<Spanned<KeyDesc>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InterpolationDesc {
+    Linear,
+    CatmullRom,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyDesc {
+    time: f64,
+    look_from: Option<[f64; 3]>,
+    look_at: Option<[f64; 3]>,
+    vfov: Option<f64>,
+    focus_dist: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ApertureDesc {
+    Disk,
+    Polygon {
+        blades: u32,
+        #[serde(default)]
+        rotation: f64,
+    },
+    Mask {
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ProjectionDesc {
+    #[default]
+    Perspective,
+    Orthographic {
+        height: f64,
+    },
+    Fisheye,
+    Equirectangular,
+}
+
+/// A material, with the spans of its fields, which are lost in parsing
+/// the material by its type.
+struct MaterialEntry {
+    desc: MaterialDesc,
+    spans: HashMap<String, Range<usize>>,
+}
+
+impl<'de> Deserialize<'de> for MaterialEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = HashMap::<String, Spanned<toml::Value>>::deserialize(deserializer)?;
+        let spans = fields
+            .iter()
+            .map(|(name, value)| (name.clone(), value.span()))
+            .collect();
+        let table = fields
+            .into_iter()
+            .map(|(name, value)| (name, value.into_inner()))
+            .collect();
+        let desc = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err: toml::de::Error| de::Error::custom(err.message()))?;
+
+        Ok(Self { desc, spans })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: [f64; 3],
+    },
+    OrenNayar {
+        albedo: [f64; 3],
+        sigma: f64,
+    },
+    Sheen {
+        albedo: [f64; 3],
+        tint: [f64; 3],
+        roughness: f64,
+    },
+    Metal {
+        albedo: [f64; 3],
+        #[serde(default)]
+        fuzz: f64,
+    },
+    Dielectric {
+        refractive_index: f64,
+    },
+    Dispersive {
+        dispersion: DispersionDesc,
+    },
+    Subsurface {
+        albedo: [f64; 3],
+        mean_free_path: f64,
+        refractive_index: f64,
+    },
+    Mix {
+        first: String,
+        second: String,
+        weight: f64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DispersionDesc {
+    Bk7,
+    Cauchy { a: f64, b: f64 },
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDesc {
+    center: [f64; 3],
+    radius: Spanned<f64>,
+    material: Spanned<String>,
+}
+
+fn color([r, g, b]: [f64; 3]) -> Color {
+    Color::new(r, g, b)
+}
+
+fn point([x, y, z]: [f64; 3]) -> Point3 {
+    Point3::new(x, y, z)
+}
+
+fn vector([x, y, z]: [f64; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+/// Fails at the span of a value unless it is positive.
+fn positive(value: &Spanned<u32>, name: &str) -> Result<u32, Invalid> {
+    match *value.get_ref() {
+        0 => Err((value.span(), format!("{} must be positive", name))),
+        v => Ok(v),
+    }
+}
+
+/// Fails at the span of a field of a material unless its value is positive.
+fn positive_field(entry: &Spanned<MaterialEntry>, name: &str, value: f64) -> Result<f64, Invalid> {
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err((
+            field_span(entry, name),
+            format!("{} must be positive", name),
+        ))
+    }
+}
+
+/// Fails at the span of a field of a material unless its value is in [0, 1].
+fn unit_field(entry: &Spanned<MaterialEntry>, name: &str, value: f64) -> Result<f64, Invalid> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err((
+            field_span(entry, name),
+            format!("{} must be in [0, 1]", name),
+        ))
+    }
+}
+
+/// The span of a field of a material, or of the whole material without it.
+fn field_span(entry: &Spanned<MaterialEntry>, name: &str) -> Range<usize> {
+    entry
+        .get_ref()
+        .spans
+        .get(name)
+        .cloned()
+        .unwrap_or_else(|| entry.span())
+}
+
+impl SceneDesc {
+    fn build(self, base: &Path) -> Result<Scene, Invalid> {
+        let renderer = self.render.build()?;
+
+        let span = self.camera.span();
+        let camera_desc = self.camera.into_inner();
+        let mut camera_builder = camera_desc
+            .builder(base)
+            .map_err(|message| (span.clone(), message))?;
+        if camera_desc.aspect_ratio.is_none() {
+            let (width, height) = (renderer.width(), renderer.height());
+            let aspect_ratio = camera_desc
+                .projection
+                .aspect_ratio(&camera_builder, width, height);
+            camera_builder = camera_builder.aspect_ratio(aspect_ratio);
+        }
+        let projection = camera_desc.projection;
+        let camera = projection
+            .build(camera_builder.clone())
+            .map_err(|err| (span, err.to_string()))?;
+
+        let mut materials = Materials {
+            descs: &self.materials,
+            built: HashMap::new(),
+            pending: vec![],
+        };
+        // all of them, in the order of the source, so that the errors
+        // of those no object refers to are reported too
+        let mut descs: Vec<_> = self.materials.iter().collect();
+        descs.sort_by_key(|(_, desc)| desc.span().start);
+        for (name, desc) in descs {
+            materials.get(name, desc.span())?;
+        }
+
+        let mut world = World::new();
+        for sphere in self.spheres {
+            let material = materials.get(sphere.material.get_ref(), sphere.material.span())?;
+            // a negative radius turns the normals inwards, for hollow glass
+            let radius = *sphere.radius.get_ref();
+            if !(radius != 0.0 && radius.is_finite()) {
+                return Err((sphere.radius.span(), "radius must not be 0".to_owned()));
+            }
+            world.add(Sphere::new(point(sphere.center), radius, material));
+        }
+
+        let animation = match self.animation {
+            Some(animation) => Some(animation.build(&camera_builder, projection)?),
+            None => None,
+        };
+
+        Ok(Scene {
+            world,
+            camera,
+            renderer,
+            camera_builder,
+            projection,
+            animation,
+        })
+    }
+}
+
+impl RenderDesc {
+    fn build(self) -> Result<Renderer, Invalid> {
+        let width = positive(&self.width, "width")?;
+        let height = positive(&self.height, "height")?;
+        let mut renderer = Renderer::new(width, height);
+
+        if let Some(spp) = self.samples_per_pixel {
+            renderer = renderer.samples_per_pixel(positive(&spp, "samples_per_pixel")?);
+        }
+        if let Some(max_depth) = self.max_depth {
+            renderer = renderer.max_depth(max_depth);
+        }
+        if let Some(seed) = self.seed {
+            renderer = renderer.seed(seed);
+        }
+        if let Some(spectral) = self.spectral {
+            renderer = renderer.spectral(spectral);
+        }
+        if let Some(sampler) = self.sampler {
+            renderer = renderer.sampler(match sampler {
+                SamplerDesc::Independent => SamplerKind::Independent,
+                SamplerDesc::Stratified => SamplerKind::Stratified,
+                SamplerDesc::Halton => SamplerKind::Halton,
+                SamplerDesc::Sobol => SamplerKind::Sobol,
+            });
+        }
+        if let Some(filter) = self.filter {
+            let span = filter.span();
+            let (FilterDesc::Box { radius }
+            | FilterDesc::Tent { radius }
+            | FilterDesc::Gaussian { radius }
+            | FilterDesc::Mitchell { radius }) = *filter.get_ref();
+            if !(radius > 0.0 && radius.is_finite()) {
+                return Err((span, "filter radius must be positive".to_owned()));
+            }
+
+            renderer = renderer.filter(match filter.into_inner() {
+                FilterDesc::Box { radius } => Filter::Box { radius },
+                FilterDesc::Tent { radius } => Filter::tent(radius),
+                FilterDesc::Gaussian { radius } => Filter::gaussian(radius),
+                FilterDesc::Mitchell { radius } => Filter::mitchell(radius),
+            });
+        }
+
+        Ok(renderer)
+    }
+}
+
+impl CameraDesc {
+    /// The camera being built, failing if an aperture mask can't be loaded.
+    fn builder(&self, base: &Path) -> Result<CameraBuilder, String> {
+        let mut builder = CameraBuilder::new(point(self.look_from), point(self.look_at));
+
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            builder = builder.aspect_ratio(aspect_ratio);
+        }
+        if let Some(vup) = self.vup {
+            builder = builder.vup(vector(vup));
+        }
+        if let Some(vfov) = self.vfov {
+            builder = builder.vfov(vfov);
+        }
+        if let Some(aperture) = self.aperture {
+            builder = builder.aperture(aperture);
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            builder = builder.focus_dist(focus_dist);
+        }
+        if let Some(shape) = &self.aperture_shape {
+            builder = builder.aperture_shape(match *shape {
+                ApertureDesc::Disk => Aperture::Disk,
+                ApertureDesc::Polygon { blades, rotation } => Aperture::polygon(blades, rotation),
+                ApertureDesc::Mask { ref path } => {
+                    let path = base.join(path);
+                    let mask = ApertureMask::load_png(&path)
+                        .map_err(|err| format!("{}: {}", path.display(), err))?;
+                    Aperture::Mask(mask)
+                }
+            });
+        }
+        if let Some(squeeze) = self.squeeze {
+            builder = builder.squeeze(squeeze);
+        }
+        if let Some(tilt) = self.tilt {
+            builder = builder.tilt(tilt);
+        }
+
+        Ok(builder)
+    }
+}
+
+impl AnimationDesc {
+    /// The path of the camera and the frame rate, failing at any key
+    /// which makes a degenerate camera.
+    fn build(
+        self,
+        camera: &CameraBuilder,
+        projection: ProjectionDesc,
+    ) -> Result<(CameraPath, f64), Invalid> {
+        let fps = match self.fps {
+            Some(fps) if !(*fps.get_ref() > 0.0 && fps.get_ref().is_finite()) => {
+                return Err((fps.span(), "fps must be positive".to_owned()));
+            }
+            Some(fps) => fps.into_inner(),
+            None => 24.0,
+        };
+
+        let mut path: Option<CameraPath> = None;
+        let keys_span = self.keys.span();
+        for key in self.keys.into_inner() {
+            let span = key.span();
+            let key = key.into_inner();
+            if !key.time.is_finite() {
+                return Err((span, "time must be finite".to_owned()));
+            }
+
+            let mut builder = camera.clone();
+            if let Some(look_from) = key.look_from {
+                builder = builder.look_from(point(look_from));
+            }
+            if let Some(look_at) = key.look_at {
+                builder = builder.look_at(point(look_at));
+            }
+            if let Some(vfov) = key.vfov {
+                builder = builder.vfov(vfov);
+            }
+            if let Some(focus_dist) = key.focus_dist {
+                builder = builder.focus_dist(focus_dist);
+            }
+            projection
+                .build(builder.clone())
+                .map_err(|err| (span.clone(), err.to_string()))?;
+
+            path = Some(match path {
+                Some(path) => path.key(key.time, builder),
+                None => CameraPath::new(key.time, builder),
+            });
+        }
+
+        let path = path.ok_or_else(|| (keys_span, "an animation needs keys".to_owned()))?;
+        let interpolation = match self.interpolation {
+            Some(InterpolationDesc::Linear) | None => Interpolation::Linear,
+            Some(InterpolationDesc::CatmullRom) => Interpolation::CatmullRom,
+        };
+
+        Ok((path.interpolation(interpolation).looped(self.looped), fps))
+    }
+}
+
+impl ProjectionDesc {
+    /// The aspect ratio of a camera for an image, such that the image
+    /// is not squeezed by an anamorphic lens.
+    fn aspect_ratio(&self, camera: &CameraBuilder, width: u32, height: u32) -> f64 {
+        let squeeze = camera.params.lens.squeeze;
+        let aspect_ratio = width as f64 / height as f64;
+
+        // an invalid squeeze is left for the camera to report
+        match self {
+            Self::Perspective if squeeze > 0.0 && squeeze.is_finite() => aspect_ratio / squeeze,
+            _ => aspect_ratio,
+        }
+    }
+
+    fn build(self, builder: CameraBuilder) -> Result<Box<dyn Camera>, CameraError> {
+        let camera: Box<dyn Camera> = match self {
+            Self::Perspective => Box::new(builder.build()?),
+            Self::Orthographic { height } => Box::new(builder.build_orthographic(height)?),
+            Self::Fisheye => Box::new(builder.build_fisheye()?),
+            Self::Equirectangular => Box::new(builder.build_equirectangular()?),
+        };
+
+        Ok(camera)
+    }
+}
+
+/// The named materials, each built once as it is first referred to.
+struct Materials<'a> {
+    descs: &'a HashMap<String, Spanned<MaterialEntry>>,
+    built: HashMap<&'a str, Material>,
+
+    /// The materials being built, to detect cyclic mixes.
+    pending: Vec<&'a str>,
+}
+
+impl<'a> Materials<'a> {
+    /// The material referred to by a name at a span of the source.
+    fn get(&mut self, name: &str, span: Range<usize>) -> Result<Material, Invalid> {
+        let (key, desc) = self
+            .descs
+            .get_key_value(name)
+            .ok_or_else(|| (span.clone(), format!("unknown material `{}`", name)))?;
+
+        if let Some(material) = self.built.get(key.as_str()) {
+            return Ok(material.clone());
+        }
+        if let Some(start) = self.pending.iter().position(|&p| p == key) {
+            let cycle: Vec<_> = self.pending[start..]
+                .iter()
+                .chain([&key.as_str()])
+                .map(|name| format!("`{}`", name))
+                .collect();
+            return Err((span, format!("cyclic materials {}", cycle.join(" -> "))));
+        }
+
+        self.pending.push(key);
+        let material = match desc.get_ref().desc {
+            MaterialDesc::Lambertian { albedo } => Material::lambertian(color(albedo)),
+            MaterialDesc::OrenNayar { albedo, sigma } => Material::oren_nayar(color(albedo), sigma),
+            MaterialDesc::Sheen {
+                albedo,
+                tint,
+                roughness,
+            } => Material::sheen(
+                color(albedo),
+                color(tint),
+                unit_field(desc, "roughness", roughness)?,
+            ),
+            MaterialDesc::Metal { albedo, fuzz } => Material::metal(color(albedo), fuzz),
+            MaterialDesc::Dielectric { refractive_index } => {
+                Material::dielectric(positive_field(desc, "refractive_index", refractive_index)?)
+            }
+            MaterialDesc::Dispersive { ref dispersion } => {
+                Material::dispersive(match *dispersion {
+                    DispersionDesc::Bk7 => Dispersion::bk7(),
+                    DispersionDesc::Cauchy { a, b } => Dispersion::Cauchy { a, b },
+                    DispersionDesc::Sellmeier { b, c } => Dispersion::Sellmeier { b, c },
+                })
+            }
+            MaterialDesc::Subsurface {
+                albedo,
+                mean_free_path,
+                refractive_index,
+            } => Material::subsurface(
+                color(albedo),
+                positive_field(desc, "mean_free_path", mean_free_path)?,
+                positive_field(desc, "refractive_index", refractive_index)?,
+            ),
+            MaterialDesc::Mix {
+                ref first,
+                ref second,
+                weight,
+            } => {
+                let weight = unit_field(desc, "weight", weight)?;
+                let first = self.get(first, desc.span())?;
+                let second = self.get(second, desc.span())?;
+                Material::mix(first, second, weight)
+            }
+        };
+        self.pending.pop();
+
+        self.built.insert(key, material.clone());
+        Ok(material)
+    }
+}
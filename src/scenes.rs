@@ -0,0 +1,223 @@
+//! Reference scenes, mostly from the "Ray Tracing in One Weekend" series.
+//!
+//! Each scene is built from a seed, so that benchmarks, tests and examples
+//! render exactly the same world. The camera is set up for the aspect ratio
+//! given in the documentation, which can be changed through its parameters.
+
+use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+use crate::{
+    Background, CameraBuilder, Color, Cuboid, Material, Perspective, Point3, Quad, RngExt, RotateY,
+    Sphere, Texture, Translate, Vec3, World,
+};
+
+/// The camera of the spheres scenes, looking at the origin from afar.
+fn spheres_camera(aperture: f64) -> Perspective {
+    CameraBuilder::new(Point3::new(13.0, 2.0, 3.0), Point3::default())
+        .vfov(20.0)
+        .aspect_ratio(3.0 / 2.0)
+        .aperture(aperture)
+        .focus_dist(10.0)
+        .build()
+        .unwrap()
+}
+
+/// The final scene of "In One Weekend": three big spheres of glass, matte and
+/// metal among many small random ones, with an aspect ratio of 3:2.
+pub fn in_one_weekend(seed: u64) -> (World, Perspective) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut world = World::new();
+
+    // add ground
+    let ground_material = Material::lambertian(Color::new(0.5, 0.5, 0.5));
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_material,
+    ));
+
+    // add three big balls
+    let glass = Material::dielectric(1.5);
+    world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, glass));
+    let matte = Material::lambertian(Color::new(0.4, 0.2, 0.1));
+    world.add(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, matte));
+    let metal = Material::metal(Color::new(0.7, 0.6, 0.5), 0.0);
+    world.add(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, metal));
+
+    // add a bunch of small balls
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.float(),
+                0.2,
+                b as f64 + 0.9 * rng.float(),
+            );
+
+            // do not collide with the big boys
+            if (center - Point3::new(0.0, 1.0, 0.0)).length() <= 1.2
+                || (center - Point3::new(-4.0, 1.0, 0.0)).length() <= 1.2
+                || (center - Point3::new(4.0, 1.0, 0.0)).length() <= 1.2
+            {
+                continue;
+            }
+
+            // add ball with random material
+            let choose_material = rng.float();
+            if choose_material < 0.8 {
+                // matte
+                let albedo = rng.color() * rng.color();
+                let material = Material::lambertian(albedo);
+                world.add(Sphere::new(center, 0.2, material));
+            } else if choose_material < 0.95 {
+                // metal
+                let albedo = rng.color_between(0.5, 1.0);
+                let fuzz = rng.float_between(0.0, 0.5);
+                let material = Material::metal(albedo, fuzz);
+                world.add(Sphere::new(center, 0.2, material));
+            } else {
+                // glass
+                let material = Material::dielectric(1.5);
+                world.add(Sphere::new(center, 0.2, material));
+            }
+        }
+    }
+
+    (world, spheres_camera(0.1))
+}
+
+/// Two big spheres with a checker texture, with an aspect ratio of 3:2.
+///
+/// The seed is unused, as there is nothing random in the scene.
+pub fn checkered_spheres(_seed: u64) -> (World, Perspective) {
+    let mut world = World::new();
+
+    let checker = Texture::checker(
+        Color::new(0.2, 0.3, 0.1).into(),
+        Color::new(0.9, 0.9, 0.9).into(),
+        0.32,
+    );
+    for y in [-10.0, 10.0] {
+        let material = Material::lambertian(checker.clone());
+        world.add(Sphere::new(Point3::new(0.0, y, 0.0), 10.0, material));
+    }
+
+    (world, spheres_camera(0.0))
+}
+
+/// A sphere on the ground, both with a marble texture from Perlin noise
+/// of the seed, with an aspect ratio of 3:2.
+pub fn perlin_spheres(seed: u64) -> (World, Perspective) {
+    let mut world = World::new();
+
+    let marble = Material::lambertian(Texture::noise(seed, 4.0));
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        marble.clone(),
+    ));
+    world.add(Sphere::new(Point3::new(0.0, 2.0, 0.0), 2.0, marble));
+
+    (world, spheres_camera(0.0))
+}
+
+/// The empty Cornell box of 555 units, lit only by the light on its
+/// ceiling, with `inner` added inside. The aspect ratio is 1:1.
+fn cornell(light: Quad, inner: [Cuboid; 2]) -> (World, Perspective) {
+    let mut world = World::new();
+    world.set_background(Background::Solid(Color::default()));
+
+    let red = Material::lambertian(Color::new(0.65, 0.05, 0.05));
+    let white = Material::lambertian(Color::new(0.73, 0.73, 0.73));
+    let green = Material::lambertian(Color::new(0.12, 0.45, 0.15));
+
+    let side = 555.0;
+    let (dx, dy, dz) = (
+        Vec3::new(side, 0.0, 0.0),
+        Vec3::new(0.0, side, 0.0),
+        Vec3::new(0.0, 0.0, side),
+    );
+    let (origin, far) = (Point3::default(), Point3::new(side, side, side));
+
+    world.add(Quad::new(Point3::new(side, 0.0, 0.0), dy, dz, green));
+    world.add(Quad::new(origin, dy, dz, red));
+    world.add(light);
+    world.add(Quad::new(origin, dx, dz, white.clone())); // floor
+    world.add(Quad::new(far, -dx, -dz, white.clone())); // ceiling
+    world.add(Quad::new(Point3::new(0.0, 0.0, side), dx, dy, white)); // back
+
+    // a tall box and a short box, turned a bit towards each other
+    let [tall, short] = inner;
+    world.add(Translate::new(
+        RotateY::new(tall, 15.0),
+        Vec3::new(265.0, 0.0, 295.0),
+    ));
+    world.add(Translate::new(
+        RotateY::new(short, -18.0),
+        Vec3::new(130.0, 0.0, 65.0),
+    ));
+
+    let camera = CameraBuilder::new(
+        Point3::new(278.0, 278.0, -800.0),
+        Point3::new(278.0, 278.0, 0.0),
+    )
+    .vfov(40.0)
+    .aspect_ratio(1.0)
+    .focus_dist(10.0)
+    .build()
+    .unwrap();
+
+    (world, camera)
+}
+
+/// The Cornell box with two white boxes, with an aspect ratio of 1:1.
+///
+/// It is lit by a small light only, so it needs many samples to converge.
+/// The seed is unused, as there is nothing random in the scene.
+pub fn cornell_box(_seed: u64) -> (World, Perspective) {
+    let white = Material::lambertian(Color::new(0.73, 0.73, 0.73));
+    let light = Material::diffuse_light(Color::new(15.0, 15.0, 15.0));
+
+    cornell(
+        Quad::new(
+            Point3::new(343.0, 554.0, 332.0),
+            Vec3::new(-130.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -105.0),
+            light,
+        ),
+        [
+            Cuboid::new(
+                Point3::default(),
+                Point3::new(165.0, 330.0, 165.0),
+                white.clone(),
+            ),
+            Cuboid::new(Point3::default(), Point3::new(165.0, 165.0, 165.0), white),
+        ],
+    )
+}
+
+/// The Cornell box with two boxes of black and white smoke under a larger
+/// light, with an aspect ratio of 1:1.
+///
+/// The seed is unused, as there is nothing random in the scene.
+pub fn cornell_smoke(_seed: u64) -> (World, Perspective) {
+    let black_smoke = Material::volume(Color::new(0.0, 0.0, 0.0), 0.01);
+    let white_smoke = Material::volume(Color::new(1.0, 1.0, 1.0), 0.01);
+    let light = Material::diffuse_light(Color::new(7.0, 7.0, 7.0));
+
+    // off the floor a bit, or rays leaving a box through its bottom would
+    // start right on the floor and slip through it
+    let lift = Point3::new(0.0, 1.0, 0.0);
+
+    cornell(
+        Quad::new(
+            Point3::new(113.0, 554.0, 127.0),
+            Vec3::new(330.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 305.0),
+            light,
+        ),
+        [
+            Cuboid::new(lift, Point3::new(165.0, 330.0, 165.0), black_smoke),
+            Cuboid::new(lift, Point3::new(165.0, 165.0, 165.0), white_smoke),
+        ],
+    )
+}
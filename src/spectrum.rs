@@ -0,0 +1,174 @@
+//! Spectral rendering utilities.
+//!
+//! In spectral mode each path carries a single wavelength instead of three
+//! RGB channels, which allows effects like dispersion. The radiance of the
+//! path is then converted back to RGB with the CIE color matching functions.
+//!
+//! Since the rest of the crate works with RGB colors, these colors are
+//! uplifted to spectra on the fly by a smooth, energy conserving basis:
+//! white stays white, and any color with components in [0, 1] yields a
+//! reflectance spectrum in [0, 1]. This is an approximation and does not
+//! reproduce arbitrary RGB colors exactly after the round trip.
+
+use once_cell::sync::Lazy;
+
+use crate::Color;
+
+/// The range of wavelengths (in nanometers) that are sampled.
+const LAMBDA_MIN: f64 = 360.0;
+const LAMBDA_MAX: f64 = 830.0;
+
+/// A piecewise Gaussian with different widths on either side of the mean.
+fn gaussian(lambda: f64, mean: f64, sigma_left: f64, sigma_right: f64) -> f64 {
+    let sigma = if lambda < mean {
+        sigma_left
+    } else {
+        sigma_right
+    };
+    let t = (lambda - mean) / sigma;
+
+    (-0.5 * t * t).exp()
+}
+
+/// The CIE 1931 color matching functions at a wavelength in nanometers,
+/// with the multi-lobe fit by Wyman, Sloan and Shirley (2013).
+fn cie_xyz(lambda: f64) -> [f64; 3] {
+    let x = 1.056 * gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2);
+    let y =
+        0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1);
+    let z =
+        1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8);
+
+    [x, y, z]
+}
+
+/// Converts CIE XYZ to linear sRGB (D65 white point).
+fn xyz_to_rgb([x, y, z]: [f64; 3]) -> [f64; 3] {
+    [
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    ]
+}
+
+/// The linear RGB of the equal-energy spectrum (constant 1),
+/// used to scale the output so that such a spectrum turns out white.
+static WHITE: Lazy<[f64; 3]> = Lazy::new(|| {
+    let mut xyz = [0.0; 3];
+    let mut lambda = LAMBDA_MIN;
+    while lambda < LAMBDA_MAX {
+        let cmf = cie_xyz(lambda + 0.5);
+        (0..3).for_each(|i| xyz[i] += cmf[i]);
+        lambda += 1.0;
+    }
+
+    xyz_to_rgb(xyz)
+});
+
+/// Samples a wavelength from a uniform random number in [0, 1).
+///
+/// The wavelengths are importance sampled roughly by the sensitivity of
+/// the human eye, as proposed in PBRT, which greatly reduces color noise.
+pub(crate) fn sample_wavelength(u: f64) -> f64 {
+    538.0 - 138.888889 * (0.85691062 - 1.82750197 * u).atanh()
+}
+
+/// The probability density of `sample_wavelength` at a wavelength.
+fn wavelength_pdf(lambda: f64) -> f64 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+
+    0.0039398042 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+/// Converts the radiance carried by a path of a sampled wavelength
+/// to an RGB color, whose expectation is the color of the full spectrum.
+pub(crate) fn to_color(lambda: f64, radiance: f64) -> Color {
+    let pdf = wavelength_pdf(lambda);
+    if pdf == 0.0 {
+        return Color::default();
+    }
+
+    let [r, g, b] = xyz_to_rgb(cie_xyz(lambda));
+    let [wr, wg, wb] = *WHITE;
+    let scale = radiance / pdf;
+
+    Color::new(scale * r / wr, scale * g / wg, scale * b / wb)
+}
+
+/// Smoothly steps from 0 to 1 as `x` goes from `a` to `b`.
+fn smoothstep(a: f64, b: f64, x: f64) -> f64 {
+    let t = ((x - a) / (b - a)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// The value of the spectrum uplifted from a RGB color at a wavelength.
+///
+/// The blue, green and red components are spread over the short, middle
+/// and long wavelengths respectively, by weights that always sum to one.
+pub(crate) fn uplift(color: Color, lambda: f64) -> f64 {
+    let s1 = smoothstep(470.0, 510.0, lambda);
+    let s2 = smoothstep(570.0, 610.0, lambda);
+
+    (1.0 - s1) * color.b() + (s1 - s2) * color.g() + s2 * color.r()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The color of the spectrum uplifted from a color, estimated with
+    /// stratified wavelengths.
+    fn round_trip(color: Color) -> Color {
+        let n = 100_000;
+        let sum = (0..n)
+            .map(|i| {
+                let lambda = sample_wavelength((i as f64 + 0.5) / n as f64);
+                to_color(lambda, uplift(color, lambda))
+            })
+            .fold(Color::default(), |sum, c| sum + c);
+
+        (1.0 / n as f64) * sum
+    }
+
+    #[test]
+    fn white_round_trips() {
+        for gray in [1.0, 0.5] {
+            let c = round_trip(Color::new(gray, gray, gray));
+            for channel in [c.r(), c.g(), c.b()] {
+                assert!((channel - gray).abs() < 1e-2, "{:?}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn uplifted_reflectance_is_bounded() {
+        let colors = [
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.2, 0.9, 0.4),
+        ];
+        for color in colors {
+            for lambda in (360..=830).step_by(10) {
+                let value = uplift(color, lambda as f64);
+                assert!((0.0..=1.0).contains(&value), "{:?} at {}", color, lambda);
+            }
+        }
+
+        // white stays white everywhere
+        let white = Color::new(1.0, 1.0, 1.0);
+        assert!((360..=830).all(|lambda| (uplift(white, lambda as f64) - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn wavelengths_are_in_range() {
+        for i in 0..=100 {
+            let lambda = sample_wavelength(i as f64 / 100.0 * 0.999_999);
+            assert!((LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda), "{}", lambda);
+            assert!(wavelength_pdf(lambda) > 0.0);
+        }
+    }
+}
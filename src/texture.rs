@@ -0,0 +1,248 @@
+//! Textures, i.e. colors that vary over a surface.
+//!
+//! A texture is evaluated at a `HitRecord`, so it may depend on the hit point
+//! in space as well as on the surface coordinates (u, v) of the hit object.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::object::HitRecord;
+use crate::{Color, Perlin};
+
+/// A color pattern that can be looked up at a hit point.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Texture {
+    /// The same color everywhere.
+    Solid { color: Color },
+
+    /// A 3D checker pattern alternating between two textures.
+    Checker {
+        even: Box<Texture>,
+        odd: Box<Texture>,
+
+        /// The side length of each cell in world units.
+        scale: f64,
+    },
+
+    /// An image wrapped around the surface by its (u, v) coordinates.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_image"))]
+    Image {
+        width: usize,
+        height: usize,
+
+        /// Pixels in row-major order, starting from the top left corner.
+        pixels: Vec<Color>,
+    },
+
+    /// A gray marble-like pattern of stripes along Z, made turbulent
+    /// by Perlin noise.
+    Noise {
+        perlin: Perlin,
+
+        /// The frequency of the stripes in world units.
+        scale: f64,
+    },
+
+    /// The vertex colors of a mesh, interpolated over each triangle.
+    /// It is white on objects without vertex colors.
+    VertexColor,
+}
+
+impl Texture {
+    pub fn solid(color: Color) -> Self {
+        Self::Solid { color }
+    }
+
+    pub fn checker(even: Texture, odd: Texture, scale: f64) -> Self {
+        Self::Checker {
+            even: Box::new(even),
+            odd: Box::new(odd),
+            scale,
+        }
+    }
+
+    /// A marble texture with Perlin noise from a seed.
+    pub fn noise(seed: u64, scale: f64) -> Self {
+        Self::Noise {
+            perlin: Perlin::new(seed),
+            scale,
+        }
+    }
+
+    /// Creates an image texture from its pixels.
+    ///
+    /// Panics if the number of pixels does not match the dimensions,
+    /// or the image is empty.
+    pub fn image(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        let ImageData {
+            width,
+            height,
+            pixels,
+        } = ImageData::try_from(RawImage {
+            width,
+            height,
+            pixels,
+        })
+        .unwrap_or_else(|err| panic!("{}", err));
+
+        Self::Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The color of the texture at the hit point.
+    pub(crate) fn value(&self, rec: &HitRecord) -> Color {
+        match self {
+            Self::Solid { color } => *color,
+
+            Self::Checker { even, odd, scale } => {
+                let cell = |x: f64| (x / scale).floor() as i64;
+                let parity = cell(rec.p.x()) + cell(rec.p.y()) + cell(rec.p.z());
+
+                if parity.rem_euclid(2) == 0 {
+                    even.value(rec)
+                } else {
+                    odd.value(rec)
+                }
+            }
+
+            Self::Image {
+                width,
+                height,
+                pixels,
+            } => {
+                // no image data, fall back to a debug color
+                if *width == 0 || *height == 0 || pixels.len() != width * height {
+                    return Color::new(0.0, 1.0, 1.0);
+                }
+
+                // wrap around, and flip v to image coordinates
+                let u = rec.u.rem_euclid(1.0);
+                let v = 1.0 - rec.v.rem_euclid(1.0);
+
+                let i = ((u * *width as f64) as usize).min(width - 1);
+                let j = ((v * *height as f64) as usize).min(height - 1);
+
+                pixels[j * width + i]
+            }
+
+            Self::Noise { perlin, scale } => {
+                let x =
+                    0.5 * (1.0 + (scale * rec.p.z() + 10.0 * perlin.turbulence(rec.p, 7)).sin());
+                Color::new(x, x, x)
+            }
+
+            Self::VertexColor => rec.vertex_color.unwrap_or(Color::new(1.0, 1.0, 1.0)),
+        }
+    }
+}
+
+impl From<Color> for Texture {
+    fn from(color: Color) -> Self {
+        Self::solid(color)
+    }
+}
+
+/// The fields of an image texture, as (de)serialized.
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+struct RawImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+/// The fields of an image texture, with as many pixels as its size.
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(try_from = "RawImage"))]
+struct ImageData {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl TryFrom<RawImage> for ImageData {
+    type Error = String;
+
+    fn try_from(raw: RawImage) -> Result<Self, String> {
+        let RawImage {
+            width,
+            height,
+            pixels,
+        } = raw;
+
+        if width == 0 || height == 0 {
+            return Err(format!("image size {}x{} is empty", width, height));
+        }
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(format!(
+                "image size mismatch: {}x{} with {} pixels",
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_image<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(usize, usize, Vec<Color>), D::Error> {
+    let ImageData {
+        width,
+        height,
+        pixels,
+    } = ImageData::deserialize(deserializer)?;
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "image size mismatch")]
+    fn image_size_mismatch() {
+        Texture::image(2, 2, vec![Color::default(); 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is empty")]
+    fn empty_image() {
+        Texture::image(0, 3, Vec::new());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn image_deserialization_is_checked() {
+        let image = |width, height, pixels: usize| {
+            let pixel = "[0.5,0.5,0.5]";
+            let pixels = vec![pixel; pixels].join(",");
+            let json = format!(
+                r#"{{"Image":{{"width":{},"height":{},"pixels":[{}]}}}}"#,
+                width, height, pixels
+            );
+            serde_json::from_str::<Texture>(&json)
+        };
+
+        let texture = image(2, 1, 2).unwrap();
+        assert_eq!(
+            texture,
+            Texture::image(2, 1, vec![Color::new(0.5, 0.5, 0.5); 2])
+        );
+
+        let err = image(2, 2, 3).unwrap_err().to_string();
+        assert!(err.contains("image size mismatch"), "{}", err);
+        let err = image(0, 2, 0).unwrap_err().to_string();
+        assert!(err.contains("is empty"), "{}", err);
+    }
+}
@@ -0,0 +1,245 @@
+//! Tone mapping, from linear radiance to displayable colors.
+//!
+//! A renderer produces colors with unbounded intensity, while a display can
+//! only show values in [0, 1]. Tone mapping compresses the former into the
+//! latter, after an exposure adjustment. The result is then encoded with
+//! the sRGB transfer function to get the output bytes.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::color::srgb_oetf;
+use crate::Color;
+
+/// A tone mapping operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ToneMap {
+    /// Clips each channel to [0, 1].
+    Clamp,
+
+    /// The simple Reinhard operator on the luminance, `L / (1 + L)`.
+    /// It never reaches white, but keeps the hue of bright colors.
+    Reinhard,
+
+    /// The extended Reinhard operator, which maps the luminance `white`
+    /// (and anything above) to pure white.
+    ExtendedReinhard { white: f64 },
+
+    /// The ACES filmic curve, as fitted by Stephen Hill.
+    Aces,
+
+    /// The AgX curve of Troy Sobotka, as approximated by Benjamin Wrensch.
+    /// Bright saturated colors gracefully desaturate to white.
+    Agx,
+}
+
+/// Multiplies a color by a 3x3 matrix given in rows.
+fn transform(m: [[f64; 3]; 3], c: Color) -> Color {
+    let row = |r: [f64; 3]| r[0] * c.r() + r[1] * c.g() + r[2] * c.b();
+
+    Color::new(row(m[0]), row(m[1]), row(m[2]))
+}
+
+/// Applies a function to each channel of a color.
+fn per_channel(c: Color, f: impl Fn(f64) -> f64) -> Color {
+    Color::new(f(c.r()), f(c.g()), f(c.b()))
+}
+
+/// Scales a color so that its luminance maps by `f`.
+fn by_luminance(c: Color, f: impl Fn(f64) -> f64) -> Color {
+    let l = c.luminance();
+    if l <= 0.0 {
+        return Color::default();
+    }
+
+    (f(l) / l) * c
+}
+
+fn aces(c: Color) -> Color {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+
+    let c = transform(INPUT, c);
+    let c = per_channel(c, |v| {
+        let a = v * (v + 0.0245786) - 0.000090537;
+        let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+        a / b
+    });
+
+    transform(OUTPUT, c)
+}
+
+fn agx(c: Color) -> Color {
+    const INSET: [[f64; 3]; 3] = [
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ];
+
+    const OUTSET: [[f64; 3]; 3] = [
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ];
+
+    // the range of exposure values mapped to [0, 1] in log space
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+
+    let c = transform(INSET, c);
+    let c = per_channel(c, |v| {
+        let x = (v.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+
+        // polynomial fit of the sigmoid contrast curve
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    });
+    let c = transform(OUTSET, c);
+
+    // the curve yields values encoded with a 2.2 gamma, linearize them back
+    per_channel(c, |v| v.max(0.0).powf(2.2))
+}
+
+impl ToneMap {
+    /// Maps a linear color to a linear color in [0, 1].
+    pub fn apply(&self, c: Color) -> Color {
+        let c = match *self {
+            Self::Clamp => c,
+            Self::Reinhard => by_luminance(c, |l| l / (1.0 + l)),
+            Self::ExtendedReinhard { white } => {
+                by_luminance(c, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            Self::Aces => aces(c),
+            Self::Agx => agx(c),
+        };
+
+        per_channel(c, |v| v.clamp(0.0, 1.0))
+    }
+}
+
+/// A tone mapping stage, with an operator and an exposure adjustment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ToneMapper {
+    operator: ToneMap,
+
+    /// The exposure adjustment in stops, i.e. colors are scaled by
+    /// `2^exposure` before applying the operator.
+    exposure: f64,
+}
+
+impl Default for ToneMapper {
+    /// Clips the colors with no exposure adjustment.
+    fn default() -> Self {
+        Self::new(ToneMap::Clamp, 0.0)
+    }
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneMap, exposure: f64) -> Self {
+        Self { operator, exposure }
+    }
+
+    /// Maps a linear color to a linear color in [0, 1].
+    pub fn map(&self, c: Color) -> Color {
+        self.operator.apply(2f64.powf(self.exposure) * c)
+    }
+
+    /// Maps a linear color to sRGB encoded bytes.
+    pub fn to_bytes(&self, c: Color) -> [u8; 3] {
+        let c = self.map(c);
+        let encode = |v: f64| (srgb_oetf(v) * 255.0).round() as u8;
+
+        [encode(c.r()), encode(c.g()), encode(c.b())]
+    }
+
+    /// Maps a linear color to sRGB encoded 16-bit values.
+    pub fn to_16bit(&self, c: Color) -> [u16; 3] {
+        let c = self.map(c);
+        let encode = |v: f64| (srgb_oetf(v) * 65535.0).round() as u16;
+
+        [encode(c.r()), encode(c.g()), encode(c.b())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [ToneMap; 4] = [
+        ToneMap::Reinhard,
+        ToneMap::ExtendedReinhard { white: 4.0 },
+        ToneMap::Aces,
+        ToneMap::Agx,
+    ];
+
+    #[test]
+    fn black_stays_black() {
+        for operator in CURVES {
+            assert_eq!(
+                operator.apply(Color::default()),
+                Color::default(),
+                "{:?}",
+                operator
+            );
+        }
+    }
+
+    /// Whether the channels of mapped colors never decrease as the color
+    /// is scaled from far below 1 to far above.
+    fn is_monotonic(operator: ToneMap, tint: Color) -> bool {
+        let mut last = Color::default();
+        (-80..=80).all(|i| {
+            let c = operator.apply(2f64.powf(i as f64 / 10.0) * tint);
+            let rises = c.r() >= last.r() && c.g() >= last.g() && c.b() >= last.b();
+            last = c;
+            rises
+        })
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        for operator in CURVES {
+            assert!(
+                is_monotonic(operator, Color::new(1.0, 1.0, 1.0)),
+                "{:?}",
+                operator
+            );
+        }
+
+        // the Reinhard operators keep the hue, while ACES and AgX shift it,
+        // e.g. a bright color gives way in some channel as it turns white
+        for operator in &CURVES[..2] {
+            assert!(
+                is_monotonic(*operator, Color::new(0.8, 0.3, 0.1)),
+                "{:?}",
+                operator
+            );
+        }
+    }
+
+    #[test]
+    fn exposure_scales() {
+        let c = Color::new(0.1, 0.2, 0.05);
+        let tone = ToneMapper::new(ToneMap::Clamp, 1.0);
+        assert_eq!(tone.map(c), Color::new(0.2, 0.4, 0.1));
+        assert_eq!(
+            ToneMapper::default().to_bytes(Color::new(1.0, 0.0, 2.0)),
+            [255, 0, 255]
+        );
+    }
+}
@@ -11,8 +11,12 @@
 
 use std::ops;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// A 3D Vector.
 #[derive(Default, Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Vec3(f64, f64, f64);
 
 impl Vec3 {
@@ -0,0 +1,40 @@
+use hooray::*;
+
+#[test]
+fn negative_colors_stay_finite() {
+    let mut fb = Framebuffer::new(4, 4);
+    for (i, c) in [-1.0, -0.5, 0.0, 2.0].iter().cycle().take(16).enumerate() {
+        fb.add_sample(i as u32 % 4, i as u32 / 4, Color::new(*c, *c, *c));
+    }
+
+    for denoiser in [Denoiser::bilateral(2), Denoiser::atrous(2)] {
+        for c in denoiser.apply(&fb, None).colors() {
+            assert!(
+                [c.r(), c.g(), c.b()].iter().all(|c| c.is_finite()),
+                "{:?}",
+                c
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn zero_edge_stop() {
+    Denoiser::atrous(2).edges(EdgeStops {
+        normal: 0.0,
+        ..EdgeStops::default()
+    });
+}
+
+#[test]
+#[should_panic(expected = "the normal AOV is not the size of the colors")]
+fn aovs_of_another_size() {
+    let (world, camera) = scenes::in_one_weekend(1);
+    let frame = Renderer::new(4, 4)
+        .samples_per_pixel(1)
+        .aovs(true)
+        .render(&world, &camera);
+
+    Denoiser::atrous(2).apply(&Framebuffer::new(4, 3), frame.aovs.as_ref());
+}
@@ -0,0 +1,13 @@
+use hooray::*;
+
+#[test]
+fn negative_and_tiny_weights_are_empty() {
+    let mut fb = Framebuffer::new(3, 1);
+    fb.add_weighted(0, 0, Color::new(1.0, 1.0, 1.0), -0.1);
+    fb.add_weighted(1, 0, Color::new(1.0, 1.0, 1.0), 1e-12);
+    fb.add_weighted(2, 0, Color::new(1.0, 1.0, 1.0), 0.5);
+
+    assert_eq!(fb.get(0, 0), Color::default());
+    assert_eq!(fb.get(1, 0), Color::default());
+    assert_eq!(fb.get(2, 0), Color::new(1.0, 1.0, 1.0));
+}
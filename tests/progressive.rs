@@ -0,0 +1,97 @@
+use hooray::scenes::in_one_weekend;
+use hooray::*;
+
+#[test]
+fn resume_matches_uninterrupted() {
+    let (world, camera) = in_one_weekend(1);
+    let renderer = Renderer::new(24, 16).samples_per_pixel(6).seed(7);
+    let single = renderer.render(&world, &camera).color;
+
+    let mut uninterrupted = Progressive::new(renderer.clone());
+    while !uninterrupted.is_done() {
+        uninterrupted.render_pass(&world, &camera, 2, |_| (), &Cancel::new());
+    }
+
+    // interrupted after the first pass, saved and resumed
+    let mut first = Progressive::new(renderer.clone());
+    first.render_pass(&world, &camera, 2, |_| (), &Cancel::new());
+    let mut checkpoint = Vec::new();
+    first.write(&mut checkpoint).unwrap();
+    drop(first);
+
+    let mut resumed = Progressive::read(renderer, checkpoint.as_slice()).unwrap();
+    assert_eq!(resumed.samples(), 2);
+    while !resumed.is_done() {
+        resumed.render_pass(&world, &camera, 2, |_| (), &Cancel::new());
+    }
+
+    let expected: Vec<Color> = uninterrupted.color().colors().collect();
+    let actual: Vec<Color> = resumed.color().colors().collect();
+    assert_eq!(actual, expected);
+
+    // the same samples as a single render, summed in another order
+    for (a, b) in actual.iter().zip(single.colors()) {
+        let diff = [a.r() - b.r(), a.g() - b.g(), a.b() - b.b()];
+        assert!(diff.iter().all(|d| d.abs() < 1e-12), "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn checkpoint_of_other_settings_is_rejected() {
+    let renderer = Renderer::new(4, 4).samples_per_pixel(2);
+    let mut checkpoint = Vec::new();
+    Progressive::new(renderer.clone())
+        .write(&mut checkpoint)
+        .unwrap();
+
+    let other = renderer.seed(3);
+    assert!(Progressive::read(other, checkpoint.as_slice()).is_err());
+}
+
+#[test]
+#[should_panic]
+fn pass_of_no_samples() {
+    let (world, camera) = in_one_weekend(1);
+    let mut progressive = Progressive::new(Renderer::new(4, 4));
+    progressive.render_pass(&world, &camera, 0, |_| (), &Cancel::new());
+}
+
+#[test]
+fn checkpoint_of_other_tile_size_is_resumed() {
+    let renderer = Renderer::new(4, 4).samples_per_pixel(2).tile_size(2);
+    let mut checkpoint = Vec::new();
+    Progressive::new(renderer.clone())
+        .write(&mut checkpoint)
+        .unwrap();
+
+    let other = renderer.tile_size(3);
+    assert!(Progressive::read(other, checkpoint.as_slice()).is_ok());
+}
+
+#[test]
+fn checkpoint_of_other_filter_is_rejected() {
+    let renderer = Renderer::new(4, 4).samples_per_pixel(2);
+    let mut checkpoint = Vec::new();
+    Progressive::new(renderer.clone())
+        .write(&mut checkpoint)
+        .unwrap();
+
+    let other = renderer.filter(Filter::tent(1.0));
+    assert!(Progressive::read(other, checkpoint.as_slice()).is_err());
+}
+
+#[test]
+fn corrupt_settings_length_is_rejected() {
+    let renderer = Renderer::new(4, 4).samples_per_pixel(2);
+    let mut checkpoint = Vec::new();
+    Progressive::new(renderer.clone())
+        .write(&mut checkpoint)
+        .unwrap();
+
+    // the length after the magic and the version
+    checkpoint[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = Progressive::read(renderer, checkpoint.as_slice())
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
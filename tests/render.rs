@@ -0,0 +1,57 @@
+use hooray::scenes::in_one_weekend;
+use hooray::*;
+
+#[test]
+fn single_pixel_rows_and_columns() {
+    let (world, camera) = in_one_weekend(1);
+
+    for (width, height) in [(1, 1), (1, 4), (4, 1)] {
+        let frame = Renderer::new(width, height)
+            .samples_per_pixel(2)
+            .render(&world, &camera);
+        for color in frame.color.colors() {
+            assert!(
+                [color.r(), color.g(), color.b()]
+                    .iter()
+                    .all(|c| c.is_finite()),
+                "{}x{}: {:?}",
+                width,
+                height,
+                color
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "samples per pixel must be positive")]
+fn no_samples_per_pixel() {
+    Renderer::new(4, 4).samples_per_pixel(0);
+}
+
+#[test]
+fn aovs_are_averaged() {
+    let (world, camera) = in_one_weekend(1);
+    let frame = Renderer::new(4, 4)
+        .samples_per_pixel(3)
+        .aovs(true)
+        .render(&world, &camera);
+
+    let aovs = frame.aovs.unwrap();
+    for normal in aovs.normal.colors() {
+        let length = (normal.r().powi(2) + normal.g().powi(2) + normal.b().powi(2)).sqrt();
+        assert!(length.is_finite() && length <= 1.0 + 1e-9, "{:?}", normal);
+    }
+}
+
+#[test]
+#[should_panic(expected = "filter radius 0 is not positive")]
+fn filter_of_no_radius() {
+    Filter::tent(0.0);
+}
+
+#[test]
+#[should_panic(expected = "filter radius NaN is not positive")]
+fn renderer_filter_of_no_radius() {
+    Renderer::new(4, 4).filter(Filter::Box { radius: f64::NAN });
+}
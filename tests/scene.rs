@@ -0,0 +1,199 @@
+use hooray::*;
+
+const TURNTABLE: &str = r#"
+[render]
+width = 60
+height = 40
+
+[camera]
+look_from = [13, 2, 3]
+look_at = [0, 0, 0]
+vfov = 20
+
+[animation]
+
+[[animation.keys]]
+time = 0
+
+[[animation.keys]]
+time = 1
+look_from = [3, 2, -13]
+"#;
+
+/// The directions of the rays through the corners of the image.
+fn corners(camera: &dyn Camera) -> Vec<Vec3> {
+    [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+        .iter()
+        .map(|&(u, v)| camera.get_ray(u, v, (0.5, 0.5)).unwrap().direction().unit())
+        .collect()
+}
+
+#[test]
+fn animation_follows_resolution() {
+    let mut scene = Scene::from_toml(TURNTABLE).unwrap();
+    scene.set_resolution(80, 80).unwrap();
+
+    // the first frame is at the first key, which is the camera of the scene
+    let frame = scene.camera_at(0).unwrap();
+    let (expected, actual) = (corners(scene.camera.as_ref()), corners(frame.as_ref()));
+    for (a, b) in expected.iter().zip(&actual) {
+        assert!((*a - *b).length() < 1e-9, "{:?} != {:?}", a, b);
+    }
+}
+
+/// The message of a scene error, which must be at a position.
+fn error_at(src: &str) -> (usize, usize, String) {
+    match Scene::from_toml(src) {
+        Err(SceneError::Invalid {
+            line,
+            column,
+            message,
+        }) => (line, column, message),
+        Err(err) => panic!("unexpected error {}", err),
+        Ok(_) => panic!("no error"),
+    }
+}
+
+const HEADER: &str = r#"[render]
+width = 60
+height = 40
+
+[camera]
+look_from = [0, 0, 5]
+look_at = [0, 0, 0]
+"#;
+
+#[test]
+fn positioned_errors() {
+    // a syntax error
+    let (line, column, _) = error_at(&format!("{}vfov = \n", HEADER));
+    assert_eq!((line, column), (8, 8));
+
+    // a value out of range
+    let src = HEADER.replace("height = 40", "height = 0");
+    let (line, column, message) = error_at(&src);
+    assert_eq!((line, column), (3, 10));
+    assert!(message.contains("height"), "{}", message);
+
+    // an unknown material, at its reference
+    let src = format!(
+        "{}\n[[spheres]]\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"missing\"\n",
+        HEADER
+    );
+    let (line, column, message) = error_at(&src);
+    assert_eq!((line, column), (12, 12));
+    assert!(message.contains("`missing`"), "{}", message);
+}
+
+#[test]
+fn material_cycles() {
+    let src = format!(
+        r#"{}
+[materials.a]
+type = "mix"
+first = "b"
+second = "c"
+weight = 0.5
+
+[materials.b]
+type = "mix"
+first = "c"
+second = "a"
+weight = 0.5
+
+[materials.c]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+"#,
+        HEADER
+    );
+    let (_, _, message) = error_at(&src);
+    assert_eq!(message, "cyclic materials `a` -> `b` -> `a`");
+}
+
+#[test]
+fn unused_materials_are_checked() {
+    let src = format!(
+        "{}\n[materials.unused]\ntype = \"mix\"\nfirst = \"nowhere\"\nsecond = \"nowhere\"\nweight = 0.5\n",
+        HEADER
+    );
+    let (line, _, message) = error_at(&src);
+    assert_eq!(line, 9);
+    assert!(message.contains("`nowhere`"), "{}", message);
+}
+
+#[test]
+fn filter_radius_is_checked() {
+    let src = HEADER.replace(
+        "height = 40",
+        "height = 40\nfilter = { type = \"gaussian\", radius = -1 }",
+    );
+    let (line, column, message) = error_at(&src);
+    assert_eq!((line, column), (4, 10));
+    assert!(message.contains("filter radius"), "{}", message);
+}
+
+/// The position and message of the error of a material, below the header.
+fn material_error(material: &str) -> (usize, usize, String) {
+    error_at(&format!(
+        "{}\n[materials.m]\n{}\n\n[materials.base]\ntype = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]\n",
+        HEADER, material
+    ))
+}
+
+#[test]
+fn sphere_radius_is_checked() {
+    let src = format!(
+        "{}\n[materials.m]\ntype = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]\n\n[[spheres]]\ncenter = [0, 0, 0]\nradius = 0\nmaterial = \"m\"\n",
+        HEADER
+    );
+    let (line, column, message) = error_at(&src);
+    assert_eq!((line, column), (15, 10));
+    assert!(message.contains("radius"), "{}", message);
+
+    // but a negative radius makes a hollow sphere
+    assert!(Scene::from_toml(&src.replace("radius = 0", "radius = -1")).is_ok());
+}
+
+#[test]
+fn refractive_index_is_checked() {
+    let (line, column, message) = material_error("type = \"dielectric\"\nrefractive_index = 0");
+    assert_eq!((line, column), (11, 20));
+    assert_eq!(message, "refractive_index must be positive");
+
+    let (line, column, message) = material_error(
+        "type = \"subsurface\"\nalbedo = [1, 1, 1]\nmean_free_path = 1\nrefractive_index = -1.3",
+    );
+    assert_eq!((line, column), (13, 20));
+    assert_eq!(message, "refractive_index must be positive");
+}
+
+#[test]
+fn mean_free_path_is_checked() {
+    let (line, column, message) = material_error(
+        "type = \"subsurface\"\nalbedo = [1, 1, 1]\nmean_free_path = 0\nrefractive_index = 1.3",
+    );
+    assert_eq!((line, column), (12, 18));
+    assert_eq!(message, "mean_free_path must be positive");
+}
+
+#[test]
+fn unit_values_are_checked() {
+    let (line, column, message) =
+        material_error("type = \"sheen\"\nalbedo = [1, 1, 1]\ntint = [1, 1, 1]\nroughness = 1.5");
+    assert_eq!((line, column), (13, 13));
+    assert_eq!(message, "roughness must be in [0, 1]");
+
+    let (line, column, message) =
+        material_error("type = \"mix\"\nfirst = \"base\"\nsecond = \"base\"\nweight = -0.5");
+    assert_eq!((line, column), (13, 10));
+    assert_eq!(message, "weight must be in [0, 1]");
+}
+
+#[test]
+fn material_fields_are_checked() {
+    let (line, _, message) =
+        material_error("type = \"dielectric\"\nrefractive_index = 1.5\nfuzz = 1");
+    assert_eq!(line, 9);
+    assert!(message.contains("unknown field `fuzz`"), "{}", message);
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "serde")]
+
+use hooray::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Serializes a value to JSON and back.
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn vectors() {
+    let v = Vec3::new(1.0, -2.5, 3.25);
+    assert_eq!(round_trip(&v), v);
+
+    let p = Point3::new(0.5, 0.0, -1e10);
+    assert_eq!(round_trip(&p), p);
+
+    let c = Color::new(0.1, 0.2, 0.3);
+    assert_eq!(round_trip(&c), c);
+}
+
+#[test]
+fn vectors_are_arrays() {
+    let json = serde_json::to_string(&Point3::new(1.0, 2.0, 3.0)).unwrap();
+    assert_eq!(json, "[1.0,2.0,3.0]");
+}
+
+#[test]
+fn materials() {
+    let checker = Texture::checker(
+        Texture::solid(Color::new(0.0, 0.0, 0.0)),
+        Texture::solid(Color::new(1.0, 1.0, 1.0)),
+        10.0,
+    );
+    let normal_map = Texture::image(1, 2, vec![Color::new(0.5, 0.5, 1.0); 2]);
+
+    let materials = [
+        Material::lambertian(Color::new(0.5, 0.5, 0.5)),
+        Material::oren_nayar(Color::new(0.8, 0.7, 0.6), 20.0),
+        Material::sheen(Color::new(0.2, 0.0, 0.1), Color::new(1.0, 1.0, 1.0), 0.3),
+        Material::metal(Color::new(0.7, 0.6, 0.5), 0.1),
+        Material::dielectric(1.5),
+        Material::dispersive(Dispersion::bk7()),
+        Material::dispersive(Dispersion::Cauchy { a: 1.5, b: 0.004 }),
+        Material::subsurface(Color::new(0.9, 0.6, 0.5), 0.05, 1.4),
+        Material::mix_by(
+            Material::metal(Color::new(0.9, 0.9, 0.9), 0.0),
+            Material::lambertian(Color::new(0.1, 0.1, 0.1)),
+            checker,
+        )
+        .with_normal_map(normal_map),
+        Material::spot_light(
+            Color::new(4.0, 4.0, 4.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            20.0,
+            30.0,
+        ),
+        Material::dielectric(1.5).with_emission(Color::new(0.5, 0.5, 1.0)),
+    ];
+
+    for material in materials {
+        assert_eq!(round_trip(&material), material);
+    }
+}
+
+#[test]
+fn spheres() {
+    let sphere = Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Material::dielectric(1.5));
+
+    let json = serde_json::to_value(&sphere).unwrap();
+    assert_eq!(serde_json::to_value(round_trip(&sphere)).unwrap(), json);
+}
+
+#[test]
+fn cameras() {
+    let params = CameraParams {
+        look_from: Point3::new(13.0, 2.0, 3.0),
+        look_at: Point3::default(),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 20.0,
+        aspect_ratio: 1.5,
+        aperture: 0.1,
+        focus_dist: 10.0,
+        lens: Lens {
+            aperture: Aperture::polygon(6, 15.0),
+            squeeze: 1.5,
+            tilt: 0.0,
+        },
+    };
+    assert_eq!(round_trip(&params), params);
+
+    // a camera is serialized as its parameters
+    let camera = Perspective::try_from(params.clone()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&camera).unwrap(),
+        serde_json::to_value(&params).unwrap()
+    );
+    assert_eq!(round_trip(&camera).params(), params);
+
+    // the lens is optional
+    let mut json = serde_json::to_value(&params).unwrap();
+    json.as_object_mut().unwrap().remove("lens");
+    let camera: Perspective = serde_json::from_value(json).unwrap();
+    assert_eq!(camera.params().lens, Lens::default());
+
+    // a mask is serialized as its weights
+    let mask = Aperture::Mask(ApertureMask::new(2, 2, vec![0.0, 1.0, 0.5, 0.25]));
+    assert_eq!(round_trip(&mask), mask);
+
+    // degenerate parameters are rejected
+    let parallel = CameraParams {
+        vup: Vec3::new(-13.0, -2.0, -3.0),
+        ..params
+    };
+    let json = serde_json::to_value(parallel).unwrap();
+    assert!(serde_json::from_value::<Perspective>(json).is_err());
+
+    // the other projections are serialized as their parameters too
+    let builder = CameraBuilder::new(Point3::new(0.0, 0.0, 5.0), Point3::default());
+    let fisheye = builder.clone().vfov(180.0).build_fisheye().unwrap();
+    let json = serde_json::to_value(&fisheye).unwrap();
+    assert_eq!(json, serde_json::to_value(fisheye.params()).unwrap());
+    assert_eq!(serde_json::to_value(round_trip(&fisheye)).unwrap(), json);
+
+    let ortho = builder.clone().build_orthographic(4.0).unwrap();
+    assert_eq!(round_trip(&ortho).height(), 4.0);
+    let pano = builder.build_equirectangular().unwrap();
+    assert_eq!(round_trip(&pano).params(), pano.params());
+
+    // and checked like the builder does
+    let mut json = serde_json::to_value(&ortho).unwrap();
+    json["height"] = (-1.0).into();
+    assert!(serde_json::from_value::<Orthographic>(json).is_err());
+    let json = serde_json::to_value(CameraParams {
+        vfov: 400.0,
+        ..fisheye.params()
+    })
+    .unwrap();
+    assert!(serde_json::from_value::<Fisheye>(json).is_err());
+    let json = serde_json::to_value(CameraParams {
+        look_at: Point3::new(0.0, 0.0, 5.0),
+        ..pano.params()
+    })
+    .unwrap();
+    assert!(serde_json::from_value::<Equirectangular>(json).is_err());
+}
+
+#[test]
+fn settings() {
+    let tone = ToneMapper::new(ToneMap::ExtendedReinhard { white: 4.0 }, 0.5);
+    assert_eq!(round_trip(&tone), tone);
+
+    let filter = Filter::mitchell(2.0);
+    assert_eq!(round_trip(&filter), filter);
+
+    assert_eq!(round_trip(&SamplerKind::Sobol), SamplerKind::Sobol);
+
+    let denoiser = Denoiser::bilateral(3);
+    assert_eq!(round_trip(&denoiser), denoiser);
+}